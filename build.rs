@@ -1,7 +1,10 @@
 // res.set_icon_with_id("file.ico", "your-icon-name-here");
 
-use windres::Build;
-
+// The icon resources are only embedded into Windows executables. Build scripts run on
+// the host, so the target has to be checked at runtime for cross compilation to work,
+// embed-resource then uses the MinGW windres on other hosts.
 fn main() {
-    Build::new().compile("resources.rc").unwrap();
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("windows") {
+        embed_resource::compile("resources.rc", embed_resource::NONE);
+    }
 }
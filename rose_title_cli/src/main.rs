@@ -0,0 +1,515 @@
+//! Headless front end for multiboxing setups that don't want a window or tray icon.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use rose_title_core::character::Character;
+use rose_title_core::helpers::{client_build_info, get_debug_info};
+use rose_title_core::jobs::{parse_language, JobNames};
+use rose_title_core::offsets::{OffsetsError, OffsetsProfiles};
+use rose_title_core::pe::PeFile;
+use rose_title_core::rules::{RuleSet, RulesError, TitleRules};
+use rose_title_core::scan_cache;
+use rose_title_core::signatures::{SignatureDatabase, SignaturesError};
+use rose_title_core::slots;
+use rose_title_core::title::{parse_title_template, TitleOptions};
+use rose_title_core::tracker::{Game, GameTracker};
+use serde::Serialize;
+
+#[derive(Parser)]
+#[command(
+    name = "rose-title-cli",
+    version,
+    about = "Renames ROSE Online windows to the logged in character"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    /// Print JSON instead of plain text
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Signature database file, defaults to signatures.toml next to the executable
+    #[arg(long, global = true)]
+    signatures: Option<PathBuf>,
+
+    /// Offsets profiles file, defaults to offsets.toml next to the executable
+    #[arg(long, global = true)]
+    offsets: Option<PathBuf>,
+
+    /// Title rules file, defaults to rules.toml next to the executable
+    #[arg(long, global = true)]
+    rules: Option<PathBuf>,
+
+    /// Scan the client even if its signature addresses are cached
+    #[arg(long, global = true)]
+    no_cache: bool,
+
+    /// Name of the offsets profile to use instead of picking one by client file version
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Parts of the character to put in the window title
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "name,job"
+    )]
+    show: Vec<TitlePart>,
+
+    /// Title template like "{name} [{job_short}] Lv{level}", replaces --show
+    #[arg(long, global = true)]
+    template: Option<String>,
+
+    /// Language of the job names read from the client: korean, english, japanese,
+    /// the index of the language in the game data or builtin for the built-in names
+    #[arg(long, global = true, default_value = "english")]
+    language: String,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the running trose.exe processes and their characters
+    List,
+    /// Scan for the signature and print the signature and player addresses
+    Scan,
+    /// Keep renaming the game windows until interrupted
+    Watch {
+        /// Seconds between two updates
+        #[arg(long, default_value_t = 5)]
+        interval: u64,
+    },
+    /// List the multibox slots of the characters, or free some of them
+    Slots {
+        /// Free the slot of this character, can be given more than once
+        #[arg(long)]
+        release: Vec<String>,
+        /// Free all slots, the characters get new ones when they are seen again
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Print the debug report
+    Debug,
+    /// Check the signatures against a trose.exe on disk, without running it
+    Check {
+        /// Path to the executable
+        path: PathBuf,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum TitlePart {
+    Name,
+    Job,
+    Level,
+    Hp,
+    Mp,
+    Zone,
+    Zuly,
+}
+
+#[derive(Serialize)]
+struct SignatureCheck {
+    signature: String,
+    rva: Option<u32>,
+    section: Option<String>,
+    pointer_rva: Option<u32>,
+    pointer_section: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct GameInfo {
+    pid: u32,
+    wine_prefix: Option<PathBuf>,
+    window_handle: Option<usize>,
+    file_version: Option<String>,
+    profile: String,
+    signature: Option<String>,
+    signature_address: usize,
+    player_address: usize,
+    /// Multibox slot of the character, if it ever got one.
+    slot: Option<u32>,
+    #[serde(flatten)]
+    character: Option<Character>,
+    title: Option<String>,
+    /// Whether a title rule leaves the window title alone.
+    excluded: bool,
+    error: Option<String>,
+}
+
+impl GameInfo {
+    /// The state of `game` after `GameTracker::read_titles`, the character and title
+    /// are the ones read then.
+    fn new(game: &Game) -> Self {
+        let title = game
+            .character
+            .as_ref()
+            .filter(|_| !game.excluded)
+            .map(|_| game.title.clone());
+
+        GameInfo {
+            pid: game.pid,
+            wine_prefix: game.wine_prefix.clone(),
+            window_handle: game.window_handle,
+            file_version: game
+                .client_build
+                .as_ref()
+                .and_then(|build| build.file_version.clone()),
+            profile: game.profile.name.clone(),
+            signature: game.signature_name.clone(),
+            signature_address: game.signature_address,
+            player_address: game.player_address,
+            slot: game.slot,
+            title,
+            excluded: game.character.is_some() && game.excluded,
+            character: game.character.clone(),
+            error: game.last_error.as_ref().map(|error| error.to_string()),
+        }
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let options = TitleOptions {
+        show_username: cli.show.contains(&TitlePart::Name),
+        show_job: cli.show.contains(&TitlePart::Job),
+        show_level: cli.show.contains(&TitlePart::Level),
+        show_hp: cli.show.contains(&TitlePart::Hp),
+        show_mp: cli.show.contains(&TitlePart::Mp),
+        show_zone: cli.show.contains(&TitlePart::Zone),
+        show_zuly: cli.show.contains(&TitlePart::Zuly),
+    };
+    let template = match &cli.template {
+        Some(source) => parse_title_template(source).unwrap_or_else(|error| {
+            eprintln!("error: invalid template: {}", error);
+            eprintln!("  {}", source);
+            eprintln!("  {}", error.marker());
+            process::exit(1);
+        }),
+        None => options.template(),
+    };
+    let profiles = match load_profiles(&cli) {
+        Ok(profiles) => profiles,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            process::exit(1);
+        }
+    };
+    let signatures = match load_signatures(&cli) {
+        Ok(signatures) => signatures,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            process::exit(1);
+        }
+    };
+    let rules = match load_rules(&cli) {
+        Ok(rules) => rules,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            process::exit(1);
+        }
+    };
+    let job_language = match cli.language.as_str() {
+        "builtin" => None,
+        language => match parse_language(language) {
+            Some(index) => Some(index),
+            None => {
+                eprintln!("error: unknown language {}", language);
+                process::exit(1);
+            }
+        },
+    };
+    let mut tracker = GameTracker::new(signatures, profiles).with_rules(rules);
+    tracker.set_job_language(job_language);
+    if let Some(path) = slots::default_path() {
+        tracker = tracker.with_slots(path);
+    }
+    if !cli.no_cache {
+        if let Some(path) = scan_cache::default_path() {
+            tracker = tracker.with_scan_cache(path);
+        }
+    }
+
+    match cli.command {
+        Command::List => {
+            tracker.find_games();
+            // slots are only looked up, they are handed out when the titles are set
+            tracker.read_titles(&template);
+            let games = sorted_games(&tracker);
+            if cli.json {
+                print_json(&games);
+                return;
+            }
+
+            if games.is_empty() {
+                println!("No trose.exe processes found");
+            }
+            for game in games {
+                let title = match (&game.title, &game.error) {
+                    _ if game.excluded => "(excluded by a title rule)".into(),
+                    (Some(title), _) => title.clone(),
+                    (None, Some(error)) => format!("(no character: {})", error),
+                    (None, None) => "(no character)".into(),
+                };
+                println!(
+                    "{:>7}  {:>3}  {}{}",
+                    game.pid,
+                    slot_label(game.slot),
+                    title,
+                    prefix_suffix(&game)
+                );
+            }
+        }
+        Command::Scan => {
+            tracker.find_games();
+            // slots are only looked up, they are handed out when the titles are set
+            tracker.read_titles(&template);
+            let games = sorted_games(&tracker);
+            if cli.json {
+                print_json(&games);
+                return;
+            }
+
+            if games.is_empty() {
+                println!("No trose.exe processes found");
+            }
+            for game in games {
+                println!(
+                    "{:>7}  signature: {} {:#x}  player: {:#x}{}",
+                    game.pid,
+                    game.signature.as_deref().unwrap_or("(none)"),
+                    game.signature_address,
+                    game.player_address,
+                    prefix_suffix(&game)
+                );
+                if let Some(error) = &game.error {
+                    println!("         error: {}", error);
+                }
+            }
+        }
+        Command::Watch { interval } => {
+            let mut reported_errors: HashMap<u32, String> = HashMap::new();
+            loop {
+                tracker.find_games();
+                let old_titles: Vec<(u32, String)> = tracker
+                    .games
+                    .values()
+                    .map(|game| (game.pid, game.title.clone()))
+                    .collect();
+                tracker.set_titles(&template);
+
+                for game in tracker.games_by_slot() {
+                    if old_titles.contains(&(game.pid, game.title.clone())) {
+                        continue;
+                    }
+                    if cli.json {
+                        println!(
+                            "{}",
+                            serde_json::json!({
+                                "pid": game.pid,
+                                "slot": game.slot,
+                                "title": game.title,
+                            })
+                        );
+                    } else {
+                        println!(
+                            "{:>7}  {:>3}  {}",
+                            game.pid,
+                            slot_label(game.slot),
+                            game.title
+                        );
+                    }
+                }
+
+                // report every error once instead of on every update
+                for game in tracker.games.values() {
+                    let error = game.last_error.as_ref().map(|error| error.to_string());
+                    if error.as_ref() == reported_errors.get(&game.pid) {
+                        continue;
+                    }
+                    match &error {
+                        Some(error) => {
+                            eprintln!("{:>7}  error: {}", game.pid, error);
+                            reported_errors.insert(game.pid, error.clone());
+                        }
+                        None => {
+                            reported_errors.remove(&game.pid);
+                        }
+                    }
+                }
+
+                thread::sleep(Duration::from_secs(interval));
+            }
+        }
+        Command::Check { path } => {
+            let pe = match PeFile::load(&path) {
+                Ok(pe) => pe,
+                Err(error) => {
+                    eprintln!("error: {}", error);
+                    process::exit(1);
+                }
+            };
+            let checks = check_signatures(&tracker.signatures, &pe);
+            let any_found = checks.iter().any(|check| check.pointer_rva.is_some());
+
+            if cli.json {
+                let build = pe.client_build();
+                print_json(&serde_json::json!({
+                    "file_version": build.file_version,
+                    "build": build.key(),
+                    "signatures": checks,
+                }));
+            } else {
+                print!("{}", client_build_info(&pe.client_build()));
+                for check in checks {
+                    match (check.rva, check.pointer_rva) {
+                        (Some(rva), Some(pointer_rva)) => println!(
+                            "{}: found at {:#x} ({}), pointer at {:#x} ({})",
+                            check.signature,
+                            rva,
+                            check.section.unwrap_or_default(),
+                            pointer_rva,
+                            check
+                                .pointer_section
+                                .as_deref()
+                                .unwrap_or("outside of sections")
+                        ),
+                        (Some(rva), None) => println!(
+                            "{}: found at {:#x}, failed to follow: {}",
+                            check.signature,
+                            rva,
+                            check.error.unwrap_or_default()
+                        ),
+                        _ => println!("{}: not found", check.signature),
+                    }
+                }
+            }
+
+            if !any_found {
+                process::exit(1);
+            }
+        }
+        Command::Slots { release, clear } => {
+            if clear {
+                tracker.slots.clear();
+            }
+            for character in &release {
+                if !tracker.slots.release(character) {
+                    eprintln!("warning: {} has no slot", character);
+                }
+            }
+            if let Err(error) = tracker.save_slots() {
+                eprintln!("error: {}", error);
+                process::exit(1);
+            }
+
+            let mut entries = tracker.slots.entries.clone();
+            entries.sort_by_key(|entry| entry.slot);
+            if cli.json {
+                print_json(&entries);
+                return;
+            }
+            if entries.is_empty() {
+                println!("No characters have a slot");
+            }
+            for entry in entries {
+                println!("{:>3}  {}", entry.slot, entry.character);
+            }
+        }
+        Command::Debug => {
+            tracker.find_games();
+            let report = get_debug_info(&tracker);
+            if cli.json {
+                print_json(&serde_json::json!({ "report": report }));
+            } else {
+                print!("{}", report);
+            }
+        }
+    }
+}
+
+fn load_profiles(cli: &Cli) -> Result<OffsetsProfiles, OffsetsError> {
+    let profiles = match &cli.offsets {
+        Some(path) => OffsetsProfiles::load(path)?,
+        None => OffsetsProfiles::load_default()?,
+    };
+
+    // a forced profile is the only one there is to pick from
+    match &cli.profile {
+        Some(name) => Ok(OffsetsProfiles {
+            profiles: vec![profiles.by_name(name)?.clone()],
+        }),
+        None => Ok(profiles),
+    }
+}
+
+fn load_rules(cli: &Cli) -> Result<RuleSet, RulesError> {
+    let rules = match &cli.rules {
+        Some(path) => TitleRules::load(path)?,
+        None => TitleRules::load_default()?,
+    };
+    // no client was read yet, only the built-in job names are known
+    rules.compile(&JobNames::default())
+}
+
+fn load_signatures(cli: &Cli) -> Result<SignatureDatabase, SignaturesError> {
+    match &cli.signatures {
+        Some(path) => SignatureDatabase::load(path),
+        None => SignatureDatabase::load_default(),
+    }
+}
+
+fn check_signatures(signatures: &SignatureDatabase, pe: &PeFile) -> Vec<SignatureCheck> {
+    let section_name = |rva: u32| pe.section_at_rva(rva).map(|section| section.name.clone());
+
+    signatures
+        .player
+        .iter()
+        .map(|entry| {
+            let found = entry.find_offline(pe);
+            let pointer_rva = found
+                .as_ref()
+                .and_then(|found| found.pointer_rva.as_ref().ok().copied());
+            SignatureCheck {
+                signature: entry.name.clone(),
+                rva: found.as_ref().map(|found| found.rva),
+                section: found.as_ref().and_then(|found| section_name(found.rva)),
+                pointer_rva,
+                pointer_section: pointer_rva.and_then(section_name),
+                error: found
+                    .and_then(|found| found.pointer_rva.err())
+                    .map(|error| error.to_string()),
+            }
+        })
+        .collect()
+}
+
+fn sorted_games(tracker: &GameTracker) -> Vec<GameInfo> {
+    let mut games: Vec<GameInfo> = tracker.games.values().map(GameInfo::new).collect();
+    games.sort_by_key(|game| (game.slot.is_none(), game.slot, game.pid));
+    games
+}
+
+fn slot_label(slot: Option<u32>) -> String {
+    slot.map(|slot| format!("#{}", slot))
+        .unwrap_or_else(|| "-".into())
+}
+
+fn prefix_suffix(game: &GameInfo) -> String {
+    game.wine_prefix
+        .as_ref()
+        .map(|prefix| format!("  [{}]", prefix.display()))
+        .unwrap_or_default()
+}
+
+fn print_json<T: Serialize>(value: &T) {
+    println!("{}", serde_json::to_string_pretty(value).unwrap());
+}
@@ -0,0 +1,60 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+use rose_title_core::helpers::sig_scan;
+use rose_title_core::process_memory::FakeProcess;
+use rose_title_core::signature::{ScanTarget, Signature};
+use rose_title_core::signatures::SignatureDatabase;
+
+const MODULE_BASE: usize = 0x1_4000_0000;
+const IMAGE_SIZE: usize = 30 * 1024 * 1024;
+
+/// Pseudo random bytes with the player signature near the end, roughly where
+/// it sits in the real client.
+fn synthetic_image(pattern: &str) -> Vec<u8> {
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    let mut image: Vec<u8> = (0..IMAGE_SIZE)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect();
+
+    let offset = IMAGE_SIZE - IMAGE_SIZE / 10;
+    for (i, byte) in pattern.split(' ').enumerate() {
+        image[offset + i] = u8::from_str_radix(byte, 16).unwrap_or(0x48);
+    }
+    image
+}
+
+fn bench_sig_scan(c: &mut Criterion) {
+    let pattern = SignatureDatabase::builtin().player.remove(0).pattern;
+    let image = synthetic_image(&pattern);
+    let signature: Signature = pattern.parse().unwrap();
+
+    let mut group = c.benchmark_group("sig_scan");
+    group.throughput(Throughput::Bytes(IMAGE_SIZE as u64));
+    group.sample_size(20);
+
+    group.bench_function("find_30mb", |b| {
+        b.iter(|| signature.find(black_box(&image)))
+    });
+
+    let process = FakeProcess::new(1).with_module("trose.exe", MODULE_BASE, image.clone());
+    group.bench_function("scan_30mb_module", |b| {
+        b.iter(|| {
+            sig_scan(
+                &process,
+                black_box(&pattern),
+                &ScanTarget::Module("trose.exe".into()),
+            )
+            .unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_sig_scan);
+criterion_main!(benches);
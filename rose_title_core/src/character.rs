@@ -0,0 +1,98 @@
+//! Snapshot of the logged in character, read from the player struct.
+
+use serde::Serialize;
+
+use crate::error::Error;
+use crate::helpers::job_id_to_name;
+use crate::jobs::JobNames;
+use crate::offsets::OffsetsProfile;
+use crate::process_memory::MemoryReader;
+use crate::remote::RemoteStruct;
+
+/// The fields of the player struct shown in titles and the windows table.
+///
+/// The name and job offsets are the ones of the current client, other clients move
+/// them through their offsets profile. The other fields have no known offsets, they
+/// are only read when the offsets profile gives one and stay `None` otherwise.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, RemoteStruct)]
+pub struct Character {
+    #[offset(0x0B10)]
+    #[string(64)]
+    pub name: String,
+    #[offset(0x3B1A)]
+    pub job_id: u32,
+    pub level: Option<u16>,
+    pub hp: Option<i32>,
+    pub max_hp: Option<i32>,
+    pub mp: Option<i32>,
+    pub max_mp: Option<i32>,
+    pub zuly: Option<i64>,
+    /// Id of the zone the character is in.
+    pub zone: Option<u16>,
+    /// Number of party members including the character, 0 when not in a party.
+    pub party_members: Option<u8>,
+}
+
+impl Character {
+    /// Reads the character at `player_address` with the offsets of `profile`.
+    pub fn read_profile(
+        process: &impl MemoryReader,
+        player_address: usize,
+        profile: &OffsetsProfile,
+    ) -> Result<Character, Error> {
+        Character::read_layout(process, player_address, &profile.character_layout())
+            .map_err(|error| error.context("reading the character"))
+    }
+
+    pub fn job_name(&self, job_names: &JobNames) -> String {
+        job_id_to_name(job_names, self.job_id)
+    }
+
+    /// `None` while the party members aren't known.
+    pub fn in_party(&self) -> Option<bool> {
+        self.party_members.map(|members| members > 0)
+    }
+
+    /// Current HP in percent of the maximum, `None` while they aren't known.
+    pub fn hp_percent(&self) -> Option<u32> {
+        percent(self.hp?, self.max_hp?)
+    }
+
+    /// Current MP in percent of the maximum, `None` while they aren't known.
+    pub fn mp_percent(&self) -> Option<u32> {
+        percent(self.mp?, self.max_mp?)
+    }
+}
+
+fn percent(current: i32, max: i32) -> Option<u32> {
+    if max <= 0 {
+        return None;
+    }
+    Some((i64::from(current.clamp(0, max)) * 100 / i64::from(max)) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_percentages() {
+        let character = Character {
+            hp: Some(750),
+            max_hp: Some(1000),
+            mp: Some(20),
+            max_mp: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(character.hp_percent(), Some(75));
+        assert_eq!(character.mp_percent(), None);
+
+        let overhealed = Character {
+            hp: Some(1200),
+            max_hp: Some(1000),
+            ..Default::default()
+        };
+        assert_eq!(overhealed.hp_percent(), Some(100));
+        assert_eq!(Character::default().hp_percent(), None);
+    }
+}
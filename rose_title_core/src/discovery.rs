@@ -0,0 +1,216 @@
+use std::path::{Path, PathBuf};
+
+use sysinfo::{PidExt, ProcessExt, System, SystemExt};
+
+pub const GAME_IMAGE_NAME: &str = "trose.exe";
+
+/// A running game client.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameProcess {
+    pub pid: u32,
+    /// The Wine prefix the client runs in, `None` for native Windows processes.
+    pub wine_prefix: Option<PathBuf>,
+    /// Where the client's executable is on disk, as seen from this tool.
+    pub image_path: Option<PathBuf>,
+}
+
+/// Finds all running game clients, including the ones hosted by Wine/Proton.
+pub fn find_game_processes(system: &System) -> Vec<GameProcess> {
+    let mut games: Vec<GameProcess> = system
+        .processes()
+        .iter()
+        .filter_map(|(pid, process)| {
+            match_game_process(
+                pid.as_u32(),
+                process.name(),
+                process.exe(),
+                process.cmd(),
+                process.environ(),
+                process.cwd(),
+            )
+        })
+        .collect();
+
+    games.sort_by_key(|game| game.pid);
+    games
+}
+
+/// Checks whether the process runs the game image.
+///
+/// Wine processes show up as `wine64-preloader` (or a truncated name), but Wine
+/// replaces `argv[0]` with the Windows path of the image, e.g. `C:\ROSE\TRose.exe`.
+/// Only `argv[0]` is checked so launchers like `wine start trose.exe` don't match.
+pub fn match_game_process(
+    pid: u32,
+    name: &str,
+    exe: &Path,
+    cmd: &[String],
+    environ: &[String],
+    cwd: &Path,
+) -> Option<GameProcess> {
+    let is_game = is_game_image(name)
+        || is_game_image(&exe.to_string_lossy())
+        || cmd.first().is_some_and(|arg0| is_game_image(arg0));
+
+    if !is_game {
+        return None;
+    }
+
+    let wine_prefix = if cfg!(windows) {
+        None
+    } else {
+        wine_prefix_from_environ(environ)
+    };
+
+    let image_path = game_image_path(exe, cmd, cwd, wine_prefix.as_deref());
+    Some(GameProcess {
+        pid,
+        wine_prefix,
+        image_path,
+    })
+}
+
+/// Finds the client executable from the process image or, for Wine, from `argv[0]`.
+///
+/// Windows paths in `argv[0]` are resolved through the drive links in the prefix's
+/// `dosdevices` directory, relative ones against the working directory.
+pub fn game_image_path(
+    exe: &Path,
+    cmd: &[String],
+    cwd: &Path,
+    wine_prefix: Option<&Path>,
+) -> Option<PathBuf> {
+    if is_game_image(&exe.to_string_lossy()) {
+        return Some(exe.into());
+    }
+
+    let arg0 = cmd.first().filter(|arg0| is_game_image(arg0))?;
+    if arg0.as_bytes().get(1) == Some(&b':') {
+        let drive = arg0[..2].to_ascii_lowercase();
+        let rest = arg0[2..].trim_start_matches(['\\', '/']).replace('\\', "/");
+        return Some(wine_prefix?.join("dosdevices").join(drive).join(rest));
+    }
+    Some(cwd.join(arg0.replace('\\', "/")))
+}
+
+/// Compares the file name of a Windows or Unix path against the game image, ignoring case.
+pub fn is_game_image(path: &str) -> bool {
+    path.rsplit(['/', '\\'])
+        .next()
+        .is_some_and(|file_name| file_name.eq_ignore_ascii_case(GAME_IMAGE_NAME))
+}
+
+/// `WINEPREFIX` if set (Proton always sets it), otherwise Wine's default `~/.wine`.
+pub fn wine_prefix_from_environ(environ: &[String]) -> Option<PathBuf> {
+    let var = |name: &str| {
+        environ.iter().find_map(|entry| {
+            entry
+                .split_once('=')
+                .filter(|(key, value)| *key == name && !value.is_empty())
+                .map(|(_, value)| value)
+        })
+    };
+
+    var("WINEPREFIX")
+        .map(PathBuf::from)
+        .or_else(|| var("HOME").map(|home| Path::new(home).join(".wine")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn matches_game_image_paths() {
+        assert!(is_game_image("trose.exe"));
+        assert!(is_game_image("TRose.EXE"));
+        assert!(is_game_image(r"C:\Program Files\ROSE Online\TRose.exe"));
+        assert!(is_game_image("/home/me/Games/rose/drive_c/ROSE/trose.exe"));
+        assert!(!is_game_image("trose.exe.bak"));
+        assert!(!is_game_image(r"C:\ROSE\xtrose.exe"));
+        assert!(!is_game_image("/usr/bin/wine64-preloader"));
+    }
+
+    #[test]
+    fn matches_wine_hosted_client_by_argv0() {
+        let game = match_game_process(
+            42,
+            "wine64-preloade",
+            Path::new("/usr/lib/wine/wine64-preloader"),
+            &strings(&[r"C:\ROSE Online\TRose.exe"]),
+            &strings(&["HOME=/home/me", "WINEPREFIX=/home/me/Games/rose"]),
+            Path::new("/home/me/Games/rose/drive_c/ROSE Online"),
+        )
+        .unwrap();
+
+        assert_eq!(game.pid, 42);
+        if cfg!(not(windows)) {
+            assert_eq!(game.wine_prefix, Some(PathBuf::from("/home/me/Games/rose")));
+            assert_eq!(
+                game.image_path,
+                Some(PathBuf::from(
+                    "/home/me/Games/rose/dosdevices/c:/ROSE Online/TRose.exe"
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn finds_game_image_path() {
+        let cwd = Path::new("/home/me/rose");
+        assert_eq!(
+            game_image_path(
+                Path::new(r"C:\ROSE\trose.exe"),
+                &strings(&["trose.exe"]),
+                cwd,
+                None
+            ),
+            Some(PathBuf::from(r"C:\ROSE\trose.exe"))
+        );
+        assert_eq!(
+            game_image_path(
+                Path::new("/usr/bin/wine64-preloader"),
+                &strings(&["TRose.exe"]),
+                cwd,
+                None
+            ),
+            Some(PathBuf::from("/home/me/rose/TRose.exe"))
+        );
+        assert_eq!(
+            game_image_path(
+                Path::new("/usr/bin/wine64-preloader"),
+                &strings(&[r"D:\trose.exe"]),
+                cwd,
+                None
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn ignores_launchers_mentioning_the_client() {
+        let game = match_game_process(
+            43,
+            "wine64",
+            Path::new("/usr/bin/wine64"),
+            &strings(&["wine64", "start", "/unix", "/home/me/rose/trose.exe"]),
+            &[],
+            Path::new("/home/me"),
+        );
+
+        assert_eq!(game, None);
+    }
+
+    #[test]
+    fn defaults_to_home_wine_prefix() {
+        assert_eq!(
+            wine_prefix_from_environ(&strings(&["WINEPREFIX=", "HOME=/home/me"])),
+            Some(PathBuf::from("/home/me/.wine"))
+        );
+        assert_eq!(wine_prefix_from_environ(&[]), None);
+    }
+}
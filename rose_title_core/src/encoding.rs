@@ -0,0 +1,69 @@
+//! Character encodings of the strings in the client's memory.
+
+use std::fmt;
+
+use encoding_rs::{EUC_KR, WINDOWS_1252};
+use serde::{Deserialize, Serialize};
+
+/// How the client stores text, set per offsets profile.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextEncoding {
+    #[default]
+    #[serde(rename = "utf-8", alias = "utf8")]
+    Utf8,
+    /// Windows-1252, a superset of the printable Latin-1 characters.
+    #[serde(rename = "cp1252", alias = "latin-1", alias = "windows-1252")]
+    Cp1252,
+    /// The Korean code page of the original client, a superset of EUC-KR.
+    #[serde(rename = "cp949", alias = "euc-kr")]
+    Cp949,
+}
+
+impl TextEncoding {
+    /// Decodes `bytes`, `None` if they aren't valid in this encoding.
+    pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+        match self {
+            TextEncoding::Utf8 => String::from_utf8(bytes.to_vec()).ok(),
+            TextEncoding::Cp1252 => decode_strict(WINDOWS_1252, bytes),
+            TextEncoding::Cp949 => decode_strict(EUC_KR, bytes),
+        }
+    }
+}
+
+fn decode_strict(encoding: &'static encoding_rs::Encoding, bytes: &[u8]) -> Option<String> {
+    encoding
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .map(|text| text.into_owned())
+}
+
+impl fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextEncoding::Utf8 => write!(f, "utf-8"),
+            TextEncoding::Cp1252 => write!(f, "cp1252"),
+            TextEncoding::Cp949 => write!(f, "cp949"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_legacy_code_pages() {
+        assert_eq!(
+            TextEncoding::Cp1252.decode(b"Ren\xE9 \x80").as_deref(),
+            Some("René €")
+        );
+        assert_eq!(
+            TextEncoding::Cp949
+                .decode(&[0xC8, 0xAB, 0xB1, 0xE6, 0xB5, 0xBF])
+                .as_deref(),
+            Some("홍길동")
+        );
+        assert_eq!(TextEncoding::Utf8.decode(b"Ren\xE9"), None);
+        // a lead byte without its trail byte
+        assert_eq!(TextEncoding::Cp949.decode(&[0x41, 0xC8]), None);
+    }
+}
@@ -0,0 +1,155 @@
+//! The errors of everything that touches a game client: its process, memory and window.
+
+use std::fmt;
+use std::io::Error as IoError;
+
+use crate::encoding::TextEncoding;
+use crate::process_memory::PointerPathError;
+use crate::signature::SignatureParseError;
+
+#[derive(Debug)]
+pub enum Error {
+    AccessDenied {
+        pid: u32,
+    },
+    ProcessExited {
+        pid: u32,
+    },
+    UnreadableAddress {
+        address: usize,
+    },
+    PartialRead {
+        address: usize,
+        expected: usize,
+        actual: usize,
+    },
+    /// No terminator within the maximum length of the string.
+    StringTooLong {
+        address: usize,
+        max_length: usize,
+    },
+    InvalidText {
+        address: usize,
+        encoding: TextEncoding,
+    },
+    InvalidSignature {
+        signature: String,
+        reason: SignatureParseError,
+    },
+    /// None of the player signatures matched in the module.
+    SignatureNotFound {
+        module: String,
+    },
+    PointerPath(Box<PointerPathError>),
+    ModuleNotFound {
+        pid: u32,
+        name: String,
+    },
+    WindowNotFound {
+        pid: u32,
+    },
+    WindowNotRenamed {
+        window: usize,
+    },
+    IOError {
+        context: String,
+        io_error: IoError,
+    },
+    /// What was being done when `error` happened.
+    Context {
+        context: String,
+        error: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Wraps the error with a description of what was being done.
+    pub fn context(self, context: impl Into<String>) -> Error {
+        Error::Context {
+            context: context.into(),
+            error: Box::new(self),
+        }
+    }
+
+    /// The error without its context.
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Context { error, .. } => error.root(),
+            error => error,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::AccessDenied { pid } => write!(f, "access to process {} denied", pid),
+            Error::ProcessExited { pid } => write!(f, "process {} has exited", pid),
+            Error::UnreadableAddress { address } => {
+                write!(f, "address {:#x} is not readable", address)
+            }
+            Error::PartialRead {
+                address,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "read only {} of {} bytes at {:#x}",
+                actual, expected, address
+            ),
+            Error::StringTooLong {
+                address,
+                max_length,
+            } => write!(
+                f,
+                "string at {:#x} is longer than {} bytes",
+                address, max_length
+            ),
+            Error::InvalidText { address, encoding } => {
+                write!(f, "string at {:#x} is not valid {}", address, encoding)
+            }
+            Error::InvalidSignature { signature, reason } => {
+                write!(f, "invalid signature \"{}\": {}", signature, reason)
+            }
+            Error::SignatureNotFound { module } => {
+                write!(f, "none of the player signatures matched in {}", module)
+            }
+            Error::PointerPath(error) => write!(f, "failed to resolve the player: {}", error),
+            Error::ModuleNotFound { pid, name } => {
+                write!(f, "module {} is not loaded in process {}", name, pid)
+            }
+            Error::WindowNotFound { pid } => write!(f, "process {} has no window", pid),
+            Error::WindowNotRenamed { window } => {
+                write!(f, "failed to set the title of window {:#x}", window)
+            }
+            Error::IOError { context, io_error } => write!(f, "{}: {}", context, io_error),
+            Error::Context { context, error } => write!(f, "{}: {}", context, error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<PointerPathError> for Error {
+    fn from(error: PointerPathError) -> Self {
+        Error::PointerPath(Box::new(error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_errors_with_context() {
+        let error = Error::UnreadableAddress { address: 0x1234 }.context("reading the job");
+        assert_eq!(
+            error.to_string(),
+            "reading the job: address 0x1234 is not readable"
+        );
+        assert!(matches!(
+            error.root(),
+            Error::UnreadableAddress { address: 0x1234 }
+        ));
+    }
+}
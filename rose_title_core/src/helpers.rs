@@ -0,0 +1,358 @@
+use chrono::{DateTime, Utc};
+use std::path::Path;
+use std::str::FromStr;
+use sysinfo::SystemExt;
+
+use crate::character::Character;
+use crate::discovery::{find_game_processes, GAME_IMAGE_NAME};
+use crate::error::Error;
+use crate::jobs::{job_names_source, language_name, JobNames};
+use crate::pe::ClientBuild;
+use crate::platform_api::{self, find_process_window};
+use crate::process_memory::{self, MemoryReader, RegionKind};
+use crate::scan_cache::client_build_key;
+use crate::signature::{ScanTarget, Signature};
+use crate::tracker::GameTracker;
+
+/// Directory in the user's cache and data directories for the files the tool writes.
+pub const APP_DIR_NAME: &str = "rose-title-changer";
+
+/// Name of the job, from the client's class table in `job_names` and the built-in
+/// English names otherwise, `None` for ids that aren't known.
+pub fn job_name(job_names: &JobNames, job_id: u32) -> Option<String> {
+    job_names
+        .get(job_id)
+        .or_else(|| builtin_job_name(job_id))
+        .map(String::from)
+}
+
+/// English name of the jobs known when the tool was written.
+pub fn builtin_job_name(job_id: u32) -> Option<&'static str> {
+    let result = match job_id {
+        0 => "Visitor",
+        111 => "Soldier",
+        121 => "Knight",
+        122 => "Champion",
+        211 => "Muse",
+        221 => "Mage",
+        222 => "Cleric",
+        311 => "Hawker",
+        321 => "Raider",
+        322 => "Scout",
+        411 => "Dealer",
+        421 => "Bourgeois",
+        422 => "Artisan",
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// Ids of the jobs `builtin_job_name` knows.
+pub const JOB_IDS: [u32; 13] = [
+    0, 111, 121, 122, 211, 221, 222, 311, 321, 322, 411, 421, 422,
+];
+
+/// Id of the job named `name` in `job_names` or the built-in names, ignoring case.
+pub fn job_id_by_name(job_names: &JobNames, name: &str) -> Option<u32> {
+    job_names.id_by_name(name).or_else(|| {
+        JOB_IDS.into_iter().find(|&job_id| {
+            builtin_job_name(job_id).is_some_and(|job| job.eq_ignore_ascii_case(name))
+        })
+    })
+}
+
+pub fn job_id_to_name(job_names: &JobNames, job_id: u32) -> String {
+    job_name(job_names, job_id).unwrap_or_else(|| "Unknown".into())
+}
+
+/// Address of the first match of `signature_str` in the memory covered by `target`.
+pub fn sig_scan(
+    process: &impl MemoryReader,
+    signature_str: &str,
+    target: &ScanTarget,
+) -> Result<Option<usize>, Error> {
+    let sig = Signature::from_str(signature_str).map_err(|reason| Error::InvalidSignature {
+        signature: signature_str.into(),
+        reason,
+    })?;
+    sig.scan_target(process, target)
+}
+
+/// Describes the client build for the debug report.
+pub fn client_build_info(build: &ClientBuild) -> String {
+    let mut text = String::new();
+    text += &format!(
+        "File version: {}\n",
+        build.file_version.as_deref().unwrap_or("unknown")
+    );
+    text += &format!("Link time: {} ({})\n", build.link_time(), build.key());
+    text += &format!(
+        ".text SHA-256: {}\n",
+        build.text_hash.as_deref().unwrap_or("no .text section")
+    );
+    text += "Sections:\n";
+    for section in &build.sections {
+        text += &format!(
+            "  {:<8} rva {:#010x} size {:#010x} raw {:#010x} flags {:#010x}\n",
+            section.name,
+            section.virtual_address,
+            section.virtual_size,
+            section.raw_size,
+            section.characteristics
+        );
+    }
+    text
+}
+
+/// Fields without an offset in the offsets profile are shown as unknown.
+fn or_unknown<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| "unknown".into(), |value| value.to_string())
+}
+
+pub fn get_debug_info(tracker: &GameTracker) -> String {
+    let signatures = &tracker.signatures;
+    let profiles = &tracker.profiles;
+    let mut debug_text = String::from("");
+
+    /* #region Log some general information */
+    let now: DateTime<Utc> = Utc::now();
+    debug_text += &format!("Date: {}\n", now);
+
+    let info = os_info::get();
+    debug_text += &format!("OS: {}\n", info);
+    debug_text += &format!(
+        "Offsets profiles: {}\n",
+        profiles
+            .profiles
+            .iter()
+            .map(|profile| profile.name.as_str())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    );
+    debug_text += &format!(
+        "Job names language: {}\n",
+        tracker
+            .job_language()
+            .map_or_else(|| "built-in".into(), language_name)
+    );
+    if let Some(error) = &tracker.slots_error {
+        debug_text += &format!("Failed to save the slots: {}\n", error);
+    }
+    if let Some(error) = &tracker.scan_cache_error {
+        debug_text += &format!("Failed to save the scan cache: {}\n", error);
+    }
+    debug_text += &format!(
+        "Signature database: version {}, player signatures: {}\n",
+        signatures.version,
+        signatures
+            .player
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    );
+    debug_text += "\n";
+    /* #endregion */
+
+    /* #region test finding processes */
+    let mut system = sysinfo::System::new();
+    system.refresh_all(); //.refresh_processes();
+
+    let game_processes = find_game_processes(&system);
+    let found_pids: Vec<u32> = game_processes.iter().map(|game| game.pid).collect();
+
+    if found_pids.is_empty() {
+        debug_text += "No";
+    } else {
+        debug_text += &found_pids.len().to_string();
+    }
+    debug_text += " trose.exe processes found!";
+    debug_text += "\nPIDs: ";
+    debug_text += &found_pids
+        .iter()
+        .map(|u| u.to_string())
+        .collect::<Vec<String>>()
+        .join(",");
+    debug_text += "\n";
+    debug_text += "\n";
+    /* #endregion */
+
+    /* #region test opening processes */
+    for game_process in game_processes {
+        let pid = game_process.pid;
+        debug_text += &format!("[{}]\n", pid);
+        if let Some(error) = tracker
+            .games
+            .get(&pid)
+            .and_then(|game| game.last_error.as_ref())
+        {
+            debug_text += &format!("Last error: {}\n", error);
+        }
+        if let Some(wine_prefix) = &game_process.wine_prefix {
+            debug_text += &format!("Wine prefix: {}\n", wine_prefix.display());
+        }
+        let job_names = tracker
+            .games
+            .get(&pid)
+            .map(|game| game.job_names.clone())
+            .unwrap_or_default();
+        debug_text += &format!("Job names: {}\n", job_names_source(&job_names));
+        if let Some(error) = game_process
+            .image_path
+            .as_deref()
+            .and_then(Path::parent)
+            .and_then(|data_dir| tracker.job_names_errors.get(data_dir))
+        {
+            debug_text += &format!("Failed to read the job names: {}\n", error);
+        }
+
+        let client_build = match &game_process.image_path {
+            Some(image_path) => {
+                debug_text += &format!("Executable: {}\n", image_path.display());
+                match ClientBuild::load(image_path) {
+                    Ok(build) => {
+                        debug_text += &client_build_info(&build);
+                        Some(build)
+                    }
+                    Err(error) => {
+                        debug_text += &format!("Failed to read executable: {}\n", error);
+                        None
+                    }
+                }
+            }
+            None => {
+                debug_text += "Failed to find the executable\n";
+                None
+            }
+        };
+        let profile = profiles.select(
+            client_build
+                .as_ref()
+                .and_then(|build| build.file_version.as_deref()),
+        );
+        debug_text += &format!("Offsets profile: {}\n", profile.name);
+
+        let process = match process_memory::open_process(pid) {
+            Ok(process) => process,
+            Err(error) => {
+                debug_text += &format!("Failed to open process: {}\n\n", error);
+                continue;
+            }
+        };
+        debug_text += "Successfully opened process\n";
+
+        let module = match process.module(GAME_IMAGE_NAME) {
+            Ok(module) => module,
+            Err(error) => {
+                debug_text += &format!("Failed to find module: {}\n\n", error);
+                continue;
+            }
+        };
+        debug_text += "Successfully found module begin and end\n";
+
+        let base_address = module.base;
+        debug_text += &format!("Module path:  {}\n", module.path.display());
+        debug_text += &format!("Module begin: {:#x}\n", base_address);
+        debug_text += &format!("Module end:   {:#x}\n", module.end());
+        match process.regions() {
+            Ok(regions) => {
+                let executable = regions.iter().filter(|r| r.protection.execute).count();
+                let private = regions
+                    .iter()
+                    .filter(|r| r.kind == RegionKind::Private)
+                    .count();
+                debug_text += &format!(
+                    "Memory regions: {} ({} executable, {} private)\n",
+                    regions.len(),
+                    executable,
+                    private
+                );
+            }
+            Err(error) => debug_text += &format!("Failed to list memory regions: {}\n", error),
+        }
+        match client_build_key(&process, base_address) {
+            Some(build) => debug_text += &format!("Client build: {}\n", build),
+            None => debug_text += "Failed to read the client build from the PE header\n",
+        }
+
+        let found = match signatures.find_player(&process, GAME_IMAGE_NAME) {
+            Ok(Some(found)) => found,
+            Ok(None) => {
+                debug_text += "None of the player signatures matched\n\n";
+                continue;
+            }
+            Err(error) => {
+                debug_text += &format!("Failed to scan for the player signatures: {}\n\n", error);
+                continue;
+            }
+        };
+        debug_text += &format!("Matched signature: {}\n", found.entry.name);
+        if !found.entry.known_builds.is_empty() {
+            debug_text += &format!("Known builds: {}\n", found.entry.known_builds.join(", "));
+        }
+
+        let player_path = found.entry.player_path();
+        debug_text += &format!("Player path: {}\n", player_path);
+
+        let trace = found.trace;
+        for (step, address) in player_path.steps.iter().zip(&trace.addresses) {
+            debug_text += &format!("Resolved {}: {:#x}\n", step, address);
+        }
+        let player_address = match trace.into_result() {
+            Ok(player_address) => player_address,
+            Err(error) => {
+                debug_text += &format!("Failed to resolve player address: {}\n\n", error);
+                continue;
+            }
+        };
+        debug_text += &format!("Successfully found player address: {:#x}\n", player_address);
+
+        let maybe_window_handle = find_process_window(process.pid());
+
+        if maybe_window_handle.is_none() {
+            debug_text += "Failed to find process window\n\n";
+            continue;
+        }
+        debug_text += "Found process window handle\n";
+        let window_handle = maybe_window_handle.unwrap();
+
+        let character = match Character::read_profile(&process, player_address, profile) {
+            Ok(character) => character,
+            Err(error) => {
+                debug_text += &format!("Failed to read the player: {}\n\n", error);
+                continue;
+            }
+        };
+
+        debug_text += &format!("Player name: {}\n", character.name);
+        debug_text += &format!(
+            "Player job: {} ({})\n",
+            character.job_id,
+            character.job_name(&job_names)
+        );
+        debug_text += &format!(
+            "Level: {}, HP: {}/{}, MP: {}/{}, zone: {}, zuly: {}, party members: {}\n",
+            or_unknown(character.level),
+            or_unknown(character.hp),
+            or_unknown(character.max_hp),
+            or_unknown(character.mp),
+            or_unknown(character.max_mp),
+            or_unknown(character.zone),
+            or_unknown(character.zuly),
+            or_unknown(character.party_members)
+        );
+
+        // try to fetch original title to revert
+        let original_title = platform_api::window_get_title(window_handle);
+
+        let set_title_result = platform_api::window_set_title(window_handle, "debug title");
+        debug_text += &format!(
+            "Tried changing window title, result: {}\n",
+            set_title_result
+        );
+        platform_api::window_set_title(window_handle, &original_title);
+    }
+    /* #endregion */
+
+    debug_text
+}
@@ -0,0 +1,179 @@
+//! Job names in the language of the player, read from the class table of the
+//! client's data directory. `helpers::job_name` falls back to the built-in English
+//! names for jobs the table doesn't have, an empty `JobNames` only has those.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::encoding::TextEncoding;
+use crate::stb::{StbError, StbFile, StlFile};
+
+/// The class list, every row is a job with the row number as its id.
+pub const CLASS_LIST_PATH: &str = "3DDATA/STB/LIST_CLASS.STB";
+/// The names of the classes, keyed by the last column of the class list.
+pub const CLASS_NAMES_PATH: &str = "3DDATA/STB/LIST_CLASS_S.STL";
+
+/// The languages of the string tables, by their index in the files.
+pub const LANGUAGES: [&str; 3] = ["korean", "english", "japanese"];
+pub const DEFAULT_LANGUAGE: usize = 1;
+
+/// Index of a language by name (ignoring case) or by its index in the files.
+pub fn parse_language(text: &str) -> Option<usize> {
+    LANGUAGES
+        .iter()
+        .position(|language| language.eq_ignore_ascii_case(text))
+        .or_else(|| text.parse().ok())
+}
+
+/// Name of the language at `index`, the index itself for languages without one.
+pub fn language_name(index: usize) -> String {
+    LANGUAGES
+        .get(index)
+        .map_or_else(|| index.to_string(), |&name| name.into())
+}
+
+/// The job names of a client in one language.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JobNames {
+    names: BTreeMap<u32, String>,
+    /// The data directory the names were read from.
+    pub data_dir: Option<PathBuf>,
+    pub language: usize,
+}
+
+impl JobNames {
+    /// Reads the class tables below `data_dir`, the directory of the client.
+    pub fn load(
+        data_dir: &Path,
+        language: usize,
+        encoding: TextEncoding,
+    ) -> Result<Self, StbError> {
+        let classes = StbFile::load(&data_dir.join(CLASS_LIST_PATH))?;
+        let names = StlFile::load(&data_dir.join(CLASS_NAMES_PATH))?;
+        let mut job_names = Self::from_tables(&classes, &names, language, encoding);
+        job_names.data_dir = Some(data_dir.into());
+        Ok(job_names)
+    }
+
+    pub fn from_tables(
+        classes: &StbFile,
+        names: &StlFile,
+        language: usize,
+        encoding: TextEncoding,
+    ) -> Self {
+        let key_column = classes.column_count().saturating_sub(1);
+        let names = (0..classes.row_count())
+            .filter_map(|row| {
+                let job_id = row as u32;
+                // rows without a key can still have an entry with their id
+                let name = match classes.text(row, key_column, TextEncoding::Utf8) {
+                    Some(key) if !key.is_empty() => names.text(&key, language, encoding),
+                    _ => names.text_by_id(job_id, language, encoding),
+                }?;
+                Some((job_id, name.trim().to_string())).filter(|(_, name)| !name.is_empty())
+            })
+            .collect();
+        JobNames {
+            names,
+            data_dir: None,
+            language,
+        }
+    }
+
+    pub fn get(&self, job_id: u32) -> Option<&str> {
+        self.names.get(&job_id).map(|name| name.as_str())
+    }
+
+    /// Id of the job named `name`, ignoring case.
+    pub fn id_by_name(&self, name: &str) -> Option<u32> {
+        self.names
+            .iter()
+            .find(|(_, job)| job.to_lowercase() == name.to_lowercase())
+            .map(|(&job_id, _)| job_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+/// Where the job names come from, for the debug report.
+pub fn job_names_source(names: &JobNames) -> String {
+    match names.data_dir.as_deref() {
+        Some(dir) => format!(
+            "{} {} names from {}",
+            names.len(),
+            language_name(names.language),
+            dir.display()
+        ),
+        None if names.is_empty() => "built-in".into(),
+        None => format!("{} {} names", names.len(), language_name(names.language)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::{job_id_by_name, job_id_to_name, job_name};
+
+    fn data_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data")
+    }
+
+    #[test]
+    fn loads_names_in_the_chosen_language() {
+        let english = JobNames::load(&data_dir(), 1, TextEncoding::Utf8).unwrap();
+        assert_eq!(english.get(0), Some("Visitor"));
+        assert_eq!(english.get(131), Some("Berserker"));
+        assert_eq!(english.get(122), None);
+        assert_eq!(english.id_by_name("berserker"), Some(131));
+        assert_eq!(english.data_dir, Some(data_dir()));
+        // the row without a key is found by its id
+        assert!(english
+            .get(132)
+            .is_some_and(|name| name.starts_with("A class")));
+
+        let korean = JobNames::load(&data_dir(), 0, TextEncoding::Utf8).unwrap();
+        assert_eq!(korean.get(111), Some("솔저"));
+        assert_eq!(korean.id_by_name("나이트"), Some(121));
+        // empty names are left to the built-in ones
+        assert_eq!(korean.get(132), None);
+
+        assert!(JobNames::load(&data_dir(), 2, TextEncoding::Utf8)
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            JobNames::load(&data_dir().join("missing"), 1, TextEncoding::Utf8),
+            Err(StbError::IOError { .. })
+        ));
+    }
+
+    #[test]
+    fn falls_back_to_the_builtin_names() {
+        let names = JobNames::load(&data_dir(), 1, TextEncoding::Utf8).unwrap();
+        assert_eq!(job_name(&names, 131).as_deref(), Some("Berserker"));
+        assert_eq!(job_name(&names, 221).as_deref(), Some("Mage"));
+        assert_eq!(job_id_by_name(&names, "BERSERKER"), Some(131));
+        assert_eq!(job_id_by_name(&names, "cleric"), Some(222));
+        assert_eq!(job_id_to_name(&names, 999), "Unknown");
+        assert!(job_names_source(&names).starts_with("5 english names from "));
+
+        let builtin = JobNames::default();
+        assert_eq!(job_name(&builtin, 131), None);
+        assert_eq!(job_name(&builtin, 221).as_deref(), Some("Mage"));
+        assert_eq!(job_names_source(&builtin), "built-in");
+    }
+
+    #[test]
+    fn parses_languages() {
+        assert_eq!(parse_language("English"), Some(1));
+        assert_eq!(parse_language("3"), Some(3));
+        assert_eq!(parse_language("klingon"), None);
+        assert_eq!(language_name(0), "korean");
+        assert_eq!(language_name(3), "3");
+    }
+}
@@ -0,0 +1,34 @@
+//! Headless core of the ROSE title changer: finds running game clients, resolves
+//! the logged in character and renames the client windows.
+
+// lets the code generated by #[derive(RemoteStruct)] refer to this crate by name
+extern crate self as rose_title_core;
+
+pub mod character;
+pub mod discovery;
+pub mod encoding;
+pub mod error;
+pub mod helpers;
+pub mod jobs;
+#[cfg(target_os = "linux")]
+pub mod linux_api;
+pub mod offsets;
+pub mod pe;
+pub mod process_memory;
+pub mod remote;
+pub mod rules;
+pub mod scan_cache;
+pub mod signature;
+pub mod signatures;
+pub mod slots;
+pub mod stb;
+pub mod template;
+pub mod title;
+pub mod tracker;
+#[cfg(windows)]
+pub mod windows_api;
+
+#[cfg(target_os = "linux")]
+pub use linux_api as platform_api;
+#[cfg(windows)]
+pub use windows_api as platform_api;
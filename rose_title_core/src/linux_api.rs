@@ -0,0 +1,218 @@
+use std::sync::Mutex;
+
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{Atom, AtomEnum, ConnectionExt, GetPropertyReply, PropMode, Window};
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+
+// Wine windows are plain X11 windows on the Linux side, so they are found and renamed through the X server
+
+// Connecting and interning the atoms takes several round trips, so the connection is kept for every
+// later lookup and only replaced once the X server stops answering
+static CONNECTION: Mutex<Option<X11>> = Mutex::new(None);
+
+struct X11 {
+    conn: RustConnection,
+    root: Window,
+    net_client_list: Atom,
+    net_wm_pid: Atom,
+    net_wm_name: Atom,
+    utf8_string: Atom,
+}
+
+impl X11 {
+    fn connect() -> Option<X11> {
+        let (conn, screen_num) = x11rb::connect(None).ok()?;
+        let root = conn.setup().roots[screen_num].root;
+        let net_client_list = intern_atom(&conn, b"_NET_CLIENT_LIST")?;
+        let net_wm_pid = intern_atom(&conn, b"_NET_WM_PID")?;
+        let net_wm_name = intern_atom(&conn, b"_NET_WM_NAME")?;
+        let utf8_string = intern_atom(&conn, b"UTF8_STRING")?;
+
+        Some(X11 {
+            conn,
+            root,
+            net_client_list,
+            net_wm_pid,
+            net_wm_name,
+            utf8_string,
+        })
+    }
+
+    fn property(&self, window: Window, property: Atom, type_: Atom) -> Option<GetPropertyReply> {
+        self.conn
+            .get_property(false, window, property, type_, 0, u32::MAX)
+            .ok()?
+            .reply()
+            .ok()
+            .filter(|reply| reply.type_ == type_)
+    }
+
+    // Window managers reparent clients into frames, so prefer their client list over the raw window tree
+    fn top_level_windows(&self) -> Vec<Window> {
+        if let Some(windows) = self
+            .property(self.root, self.net_client_list, AtomEnum::WINDOW.into())
+            .and_then(|reply| reply.value32().map(|windows| windows.collect()))
+        {
+            return windows;
+        }
+
+        self.conn
+            .query_tree(self.root)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|tree| tree.children)
+            .unwrap_or_default()
+    }
+
+    fn window_pid(&self, window: Window) -> Option<u32> {
+        self.property(window, self.net_wm_pid, AtomEnum::CARDINAL.into())?
+            .value32()?
+            .next()
+    }
+}
+
+fn intern_atom(conn: &RustConnection, name: &[u8]) -> Option<Atom> {
+    Some(conn.intern_atom(false, name).ok()?.reply().ok()?.atom)
+}
+
+/// Runs `f` with the shared connection, connecting first if there is none yet.
+fn with_x11<T>(f: impl FnOnce(&X11) -> Option<T>) -> Option<T> {
+    let mut connection = CONNECTION.lock().unwrap_or_else(|error| error.into_inner());
+    if connection.is_none() {
+        *connection = X11::connect();
+    }
+    let x11 = connection.as_ref()?;
+
+    let result = f(x11);
+    // Errors of requests whose cookies were dropped arrive as events, nothing else reads them
+    while let Ok(Some(_)) = x11.conn.poll_for_event() {}
+    // Most failures are about the window, a connection that can't round-trip anymore is dropped
+    if result.is_none() && x11.conn.sync().is_err() {
+        *connection = None;
+    }
+    result
+}
+
+pub fn window_get_title(window: usize) -> String {
+    with_x11(|x11| {
+        let window = window as Window;
+
+        if let Some(reply) = x11.property(window, x11.net_wm_name, x11.utf8_string) {
+            return Some(String::from_utf8_lossy(&reply.value).into_owned());
+        }
+
+        x11.property(window, AtomEnum::WM_NAME.into(), AtomEnum::STRING.into())
+            .map(|reply| reply.value.iter().map(|&c| c as char).collect())
+    })
+    .unwrap_or_default()
+}
+
+pub fn window_set_title(window: usize, title: &str) -> bool {
+    if window == 0 {
+        return false;
+    }
+
+    with_x11(|x11| set_title(x11, window as Window, title).then_some(())).is_some()
+}
+
+fn set_title(x11: &X11, window: Window, title: &str) -> bool {
+    // WM_NAME is a Latin-1 STRING, characters outside of it can only go into _NET_WM_NAME
+    let latin1_title: Vec<u8> = title
+        .chars()
+        .map(|c| u8::try_from(c).unwrap_or(b'?'))
+        .collect();
+
+    let changed = x11.conn.change_property8(
+        PropMode::REPLACE,
+        window,
+        x11.net_wm_name,
+        x11.utf8_string,
+        title.as_bytes(),
+    );
+    if changed.is_err() {
+        return false;
+    }
+
+    let changed = x11.conn.change_property8(
+        PropMode::REPLACE,
+        window,
+        AtomEnum::WM_NAME,
+        AtomEnum::STRING,
+        &latin1_title,
+    );
+    if changed.is_err() {
+        return false;
+    }
+
+    // Round-trip so errors about the window (e.g. it was closed in the meantime) are reported here
+    x11.conn.sync().is_ok()
+}
+
+pub fn find_process_window(pid: u32) -> Option<usize> {
+    with_x11(|x11| {
+        x11.top_level_windows()
+            .into_iter()
+            .find(|&window| x11.window_pid(window) == Some(pid))
+            .map(|window| window as usize)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use x11rb::protocol::xproto::{CreateWindowAux, WindowClass};
+    use x11rb::COPY_DEPTH_FROM_PARENT;
+
+    fn create_window(x11: &X11, pid: u32) -> Window {
+        let window = x11.conn.generate_id().unwrap();
+        x11.conn
+            .create_window(
+                COPY_DEPTH_FROM_PARENT,
+                window,
+                x11.root,
+                0,
+                0,
+                64,
+                64,
+                0,
+                WindowClass::INPUT_OUTPUT,
+                0,
+                &CreateWindowAux::new(),
+            )
+            .unwrap();
+        x11.conn
+            .change_property32(
+                PropMode::REPLACE,
+                window,
+                x11.net_wm_pid,
+                AtomEnum::CARDINAL,
+                &[pid],
+            )
+            .unwrap();
+        x11.conn.sync().unwrap();
+        window
+    }
+
+    #[test]
+    #[ignore = "needs an X server, run with `xvfb-run cargo test -- --ignored`"]
+    fn finds_and_renames_window_by_pid() {
+        let x11 = X11::connect().expect("no X server");
+        let pid = 0x7fff_0000 + std::process::id() % 0xffff;
+        let window = create_window(&x11, pid);
+
+        assert_eq!(find_process_window(pid), Some(window as usize));
+        assert_eq!(find_process_window(pid + 1), None);
+
+        assert!(window_set_title(window as usize, "Xikeon - Mage ✓"));
+        assert_eq!(window_get_title(window as usize), "Xikeon - Mage ✓");
+
+        let wm_name = x11
+            .property(window, AtomEnum::WM_NAME.into(), AtomEnum::STRING.into())
+            .unwrap();
+        assert_eq!(wm_name.value, b"Xikeon - Mage ?");
+
+        // the lookups share one connection
+        assert!(CONNECTION.lock().unwrap().is_some());
+    }
+}
@@ -0,0 +1,298 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::Error as IoError;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::character::Character;
+use crate::encoding::TextEncoding;
+use crate::remote::Layout;
+
+const BUILTIN_PROFILES: &str = include_str!("../offsets.toml");
+pub const OFFSETS_FILE_NAME: &str = "offsets.toml";
+
+/// Offsets of the fields of the player struct for one client version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OffsetsProfile {
+    pub name: String,
+    /// Client versions this profile is known to work on, empty for any version.
+    #[serde(default)]
+    pub client_versions: Vec<String>,
+    pub name_offset: usize,
+    pub job_offset: usize,
+    /// Encoding of the strings in the player struct.
+    #[serde(default)]
+    pub encoding: TextEncoding,
+    /// Offsets of the other `Character` fields, the ones not listed aren't read.
+    #[serde(default)]
+    pub character_offsets: BTreeMap<String, usize>,
+}
+
+impl Default for OffsetsProfile {
+    fn default() -> Self {
+        OffsetsProfiles::builtin().profiles.remove(0)
+    }
+}
+
+#[derive(Debug)]
+pub enum OffsetsError {
+    IOError { path: PathBuf, io_error: IoError },
+    ParseError { path: PathBuf, message: String },
+    InvalidProfile { name: String, reason: String },
+    NoProfiles,
+    UnknownProfile { name: String },
+}
+
+impl fmt::Display for OffsetsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OffsetsError::IOError { path, io_error } => {
+                write!(f, "failed to read {}: {}", path.display(), io_error)
+            }
+            OffsetsError::ParseError { path, message } => {
+                write!(f, "failed to parse {}: {}", path.display(), message)
+            }
+            OffsetsError::InvalidProfile { name, reason } => {
+                write!(f, "invalid offsets profile \"{}\": {}", name, reason)
+            }
+            OffsetsError::NoProfiles => write!(f, "no offsets profiles defined"),
+            OffsetsError::UnknownProfile { name } => {
+                write!(f, "no offsets profile named \"{}\"", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OffsetsError {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OffsetsProfiles {
+    #[serde(rename = "profile")]
+    pub profiles: Vec<OffsetsProfile>,
+}
+
+impl OffsetsProfiles {
+    /// The profiles shipped with this release.
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN_PROFILES, Path::new("<builtin>")).expect("invalid builtin offsets")
+    }
+
+    pub fn load(path: &Path) -> Result<Self, OffsetsError> {
+        let text = fs::read_to_string(path).map_err(|io_error| OffsetsError::IOError {
+            path: path.into(),
+            io_error,
+        })?;
+        Self::parse(&text, path)
+    }
+
+    /// Loads `offsets.toml` next to the executable if there is one, otherwise the builtin profiles.
+    pub fn load_default() -> Result<Self, OffsetsError> {
+        match default_path() {
+            Some(path) if path.exists() => Self::load(&path),
+            _ => Ok(Self::builtin()),
+        }
+    }
+
+    pub fn parse(text: &str, path: &Path) -> Result<Self, OffsetsError> {
+        let profiles: OffsetsProfiles =
+            toml::from_str(text).map_err(|error| OffsetsError::ParseError {
+                path: path.into(),
+                message: error.to_string(),
+            })?;
+        profiles.validate()?;
+        Ok(profiles)
+    }
+
+    pub fn validate(&self) -> Result<(), OffsetsError> {
+        if self.profiles.is_empty() {
+            return Err(OffsetsError::NoProfiles);
+        }
+
+        for (index, profile) in self.profiles.iter().enumerate() {
+            if self.profiles[..index]
+                .iter()
+                .any(|other| other.name == profile.name)
+            {
+                return Err(invalid(profile, "duplicate profile name"));
+            }
+            profile.validate()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn by_name(&self, name: &str) -> Result<&OffsetsProfile, OffsetsError> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.name == name)
+            .ok_or_else(|| OffsetsError::UnknownProfile { name: name.into() })
+    }
+
+    /// Picks the profile listing `client_version`, falling back to the first generic profile.
+    pub fn select(&self, client_version: Option<&str>) -> &OffsetsProfile {
+        client_version
+            .and_then(|version| {
+                self.profiles
+                    .iter()
+                    .find(|profile| profile.client_versions.iter().any(|v| v == version))
+            })
+            .or_else(|| {
+                self.profiles
+                    .iter()
+                    .find(|profile| profile.client_versions.is_empty())
+            })
+            .unwrap_or(&self.profiles[0])
+    }
+}
+
+impl OffsetsProfile {
+    pub fn validate(&self) -> Result<(), OffsetsError> {
+        if self.name.trim().is_empty() {
+            return Err(invalid(self, "name is empty"));
+        }
+        if self.name_offset == self.job_offset {
+            return Err(invalid(self, "name_offset and job_offset are the same"));
+        }
+        for field in self.character_offsets.keys() {
+            if field == "name" || field == "job_id" {
+                return Err(invalid(
+                    self,
+                    &format!("{} is moved with name_offset or job_offset", field),
+                ));
+            }
+            if !Layout::of::<Character>().has_field(field) {
+                return Err(invalid(self, &format!("unknown character field {}", field)));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Where the fields of the `Character` are for this profile.
+    pub fn character_layout(&self) -> Layout {
+        let mut layout = Layout::of::<Character>().with_encoding(self.encoding);
+        layout.set_offset("name", self.name_offset);
+        layout.set_offset("job_id", self.job_offset);
+        for (field, offset) in &self.character_offsets {
+            layout.set_offset(field, *offset);
+        }
+        layout
+    }
+}
+
+fn invalid(profile: &OffsetsProfile, reason: &str) -> OffsetsError {
+    OffsetsError::InvalidProfile {
+        name: profile.name.clone(),
+        reason: reason.into(),
+    }
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    Some(
+        std::env::current_exe()
+            .ok()?
+            .with_file_name(OFFSETS_FILE_NAME),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<OffsetsProfiles, OffsetsError> {
+        OffsetsProfiles::parse(text, Path::new("test.toml"))
+    }
+
+    const PROFILES: &str = r#"
+[[profile]]
+name = "old"
+client_versions = ["1.0.0.100"]
+name_offset = 0x0A00
+job_offset = 0x3A00
+encoding = "euc-kr"
+
+[[profile]]
+name = "generic"
+name_offset = 0x0B10
+job_offset = 0x3B1A
+
+[profile.character_offsets]
+level = 0x3B20
+"#;
+
+    #[test]
+    fn builtin_profile_matches_known_offsets() {
+        let profile = OffsetsProfile::default();
+        assert_eq!(profile.name_offset, 0x0B10);
+        assert_eq!(profile.job_offset, 0x3B1A);
+    }
+
+    #[test]
+    fn selects_profile_by_client_version() {
+        let profiles = parse(PROFILES).unwrap();
+        assert_eq!(profiles.select(Some("1.0.0.100")).name, "old");
+        assert_eq!(profiles.select(Some("1.0.0.200")).name, "generic");
+        assert_eq!(profiles.select(None).name, "generic");
+        assert_eq!(
+            profiles.select(Some("1.0.0.100")).encoding,
+            TextEncoding::Cp949
+        );
+        assert_eq!(profiles.select(None).encoding, TextEncoding::Utf8);
+        assert_eq!(profiles.by_name("old").unwrap().name_offset, 0x0A00);
+        assert!(profiles.by_name("missing").is_err());
+    }
+
+    #[test]
+    fn moves_character_fields() {
+        let profiles = parse(PROFILES).unwrap();
+        let old = profiles.by_name("old").unwrap().character_layout();
+        assert_eq!(old.offset("name"), Some(0x0A00));
+        assert_eq!(old.offset("job_id"), Some(0x3A00));
+        assert_eq!(old.offset("level"), None);
+
+        let generic = profiles.by_name("generic").unwrap().character_layout();
+        assert_eq!(generic.offset("level"), Some(0x3B20));
+        assert_eq!(generic.offset("zuly"), None);
+    }
+
+    #[test]
+    fn builtin_profile_matches_character_offsets() {
+        let profile = OffsetsProfile::default();
+        assert_eq!(
+            profile.character_layout(),
+            Layout::of::<Character>().with_encoding(profile.encoding)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_profiles() {
+        let error =
+            parse(&PROFILES.replace("job_offset = 0x3A00", "job_offset = 0x0A00")).unwrap_err();
+        assert!(matches!(error, OffsetsError::InvalidProfile { ref name, .. } if name == "old"));
+
+        let error = parse(&PROFILES.replace("\"generic\"", "\"old\"")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid offsets profile \"old\": duplicate profile name"
+        );
+
+        let error = parse(&PROFILES.replace("level = ", "lvl = ")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid offsets profile \"generic\": unknown character field lvl"
+        );
+        let error = parse(&PROFILES.replace("level = ", "name = ")).unwrap_err();
+        assert!(matches!(error, OffsetsError::InvalidProfile { .. }));
+
+        assert!(matches!(
+            parse("profile = []").unwrap_err(),
+            OffsetsError::NoProfiles
+        ));
+        assert!(matches!(
+            parse("[[profile]]\nname = \"x\"").unwrap_err(),
+            OffsetsError::ParseError { .. }
+        ));
+    }
+}
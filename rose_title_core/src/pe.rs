@@ -0,0 +1,586 @@
+//! Just enough of the PE format to tell client builds apart: headers, section
+//! table, the fixed file version resource and a hash of the code.
+
+use std::fmt;
+use std::fs;
+use std::io::Error as IoError;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDateTime;
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+use crate::process_memory::{MemoryReader, MemoryRegion, Module, Protection, RegionKind};
+use crate::signature::Signature;
+
+const IMAGE_SCN_MEM_EXECUTE: u32 = 0x2000_0000;
+const IMAGE_SCN_MEM_READ: u32 = 0x4000_0000;
+const IMAGE_SCN_MEM_WRITE: u32 = 0x8000_0000;
+const RT_VERSION: u32 = 16;
+const RESOURCE_DIRECTORY_INDEX: usize = 2;
+const VS_FIXEDFILEINFO_SIGNATURE: u32 = 0xFEEF_04BD;
+
+#[derive(Debug)]
+pub enum PeError {
+    IOError { path: PathBuf, io_error: IoError },
+    InvalidHeader { reason: String },
+    Truncated { offset: usize },
+}
+
+impl fmt::Display for PeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PeError::IOError { path, io_error } => {
+                write!(f, "failed to read {}: {}", path.display(), io_error)
+            }
+            PeError::InvalidHeader { reason } => write!(f, "invalid PE file: {}", reason),
+            PeError::Truncated { offset } => {
+                write!(f, "PE file ends before offset {:#x}", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PeError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeSection {
+    pub name: String,
+    pub virtual_address: u32,
+    pub virtual_size: u32,
+    pub raw_offset: u32,
+    pub raw_size: u32,
+    pub characteristics: u32,
+}
+
+impl PeSection {
+    pub fn is_executable(&self) -> bool {
+        self.characteristics & IMAGE_SCN_MEM_EXECUTE != 0
+    }
+
+    pub fn protection(&self) -> Protection {
+        Protection {
+            read: self.characteristics & IMAGE_SCN_MEM_READ != 0,
+            write: self.characteristics & IMAGE_SCN_MEM_WRITE != 0,
+            execute: self.is_executable(),
+        }
+    }
+}
+
+/// A PE file read from disk.
+pub struct PeFile {
+    data: Vec<u8>,
+    /// Where the file was loaded from, `None` when parsed from memory.
+    pub path: Option<PathBuf>,
+    pub machine: u16,
+    pub time_date_stamp: u32,
+    pub image_base: u64,
+    pub size_of_image: u32,
+    pub sections: Vec<PeSection>,
+    resource_directory: Option<(u32, u32)>,
+}
+
+impl PeFile {
+    pub fn load(path: &Path) -> Result<Self, PeError> {
+        let data = fs::read(path).map_err(|io_error| PeError::IOError {
+            path: path.into(),
+            io_error,
+        })?;
+        let mut pe = Self::parse(data)?;
+        pe.path = Some(path.into());
+        Ok(pe)
+    }
+
+    pub fn parse(data: Vec<u8>) -> Result<Self, PeError> {
+        if data.get(..2) != Some(b"MZ") {
+            return Err(invalid("missing MZ signature"));
+        }
+        let pe_header = u32_at(&data, 0x3C)? as usize;
+        if data.get(pe_header..pe_header + 4) != Some(b"PE\0\0") {
+            return Err(invalid("missing PE signature"));
+        }
+
+        let file_header = pe_header + 4;
+        let machine = u16_at(&data, file_header)?;
+        let number_of_sections = u16_at(&data, file_header + 2)? as usize;
+        let time_date_stamp = u32_at(&data, file_header + 4)?;
+        let size_of_optional_header = u16_at(&data, file_header + 16)? as usize;
+
+        let optional_header = file_header + 20;
+        let (image_base, data_directories) = match u16_at(&data, optional_header)? {
+            0x10B => (u32_at(&data, optional_header + 28)? as u64, 96),
+            0x20B => (u64_at(&data, optional_header + 24)?, 112),
+            magic => {
+                return Err(invalid(&format!(
+                    "unknown optional header magic {:#x}",
+                    magic
+                )))
+            }
+        };
+        let size_of_image = u32_at(&data, optional_header + 56)?;
+        let number_of_rva_and_sizes =
+            u32_at(&data, optional_header + data_directories - 4)? as usize;
+        let resource_directory = if number_of_rva_and_sizes > RESOURCE_DIRECTORY_INDEX {
+            let entry = optional_header + data_directories + RESOURCE_DIRECTORY_INDEX * 8;
+            Some((u32_at(&data, entry)?, u32_at(&data, entry + 4)?)).filter(|&(rva, _)| rva != 0)
+        } else {
+            None
+        };
+
+        let section_table = optional_header + size_of_optional_header;
+        let sections = (0..number_of_sections)
+            .map(|index| {
+                let header = section_table + index * 40;
+                let name = bytes_at(&data, header, 8)?;
+                let name_length = name.iter().position(|&byte| byte == 0).unwrap_or(8);
+                Ok(PeSection {
+                    name: String::from_utf8_lossy(&name[..name_length]).into(),
+                    virtual_size: u32_at(&data, header + 8)?,
+                    virtual_address: u32_at(&data, header + 12)?,
+                    raw_size: u32_at(&data, header + 16)?,
+                    raw_offset: u32_at(&data, header + 20)?,
+                    characteristics: u32_at(&data, header + 36)?,
+                })
+            })
+            .collect::<Result<Vec<_>, PeError>>()?;
+
+        Ok(PeFile {
+            data,
+            path: None,
+            machine,
+            time_date_stamp,
+            image_base,
+            size_of_image,
+            sections,
+            resource_directory,
+        })
+    }
+
+    pub fn section(&self, name: &str) -> Option<&PeSection> {
+        self.sections.iter().find(|section| section.name == name)
+    }
+
+    /// The bytes of `section` stored in the file.
+    pub fn section_data(&self, section: &PeSection) -> &[u8] {
+        let begin = (section.raw_offset as usize).min(self.data.len());
+        let end = (begin + section.raw_size as usize).min(self.data.len());
+        &self.data[begin..end]
+    }
+
+    pub fn section_at_rva(&self, rva: u32) -> Option<&PeSection> {
+        self.sections.iter().find(|section| {
+            rva.checked_sub(section.virtual_address)
+                .is_some_and(|offset| offset < section.virtual_size.max(section.raw_size))
+        })
+    }
+
+    /// RVA of the first match of `signature` in the executable sections.
+    pub fn find_signature(&self, signature: &Signature) -> Option<u32> {
+        self.sections
+            .iter()
+            .filter(|section| section.is_executable())
+            .find_map(|section| {
+                let offset = signature.find(self.section_data(section))?;
+                Some(section.virtual_address + offset as u32)
+            })
+    }
+
+    /// How many bytes are mapped from `rva` on until the end of its section, and
+    /// where they are in the file, `None` for the zero filled tail of a section.
+    fn mapping_at(&self, rva: u32) -> Option<(usize, Option<usize>)> {
+        let first_section = self
+            .sections
+            .iter()
+            .map(|section| section.virtual_address)
+            .min()
+            .unwrap_or(0);
+        if rva < first_section {
+            // the headers are mapped as they are in the file
+            let headers_size = (first_section as usize).min(self.data.len());
+            let rva = rva as usize;
+            return (rva < headers_size).then_some((headers_size - rva, Some(rva)));
+        }
+
+        let section = self.section_at_rva(rva)?;
+        let offset = rva - section.virtual_address;
+        let mapped_size = section.virtual_size.max(section.raw_size);
+        if offset < section.raw_size {
+            Some((
+                (section.raw_size - offset) as usize,
+                Some(section.raw_offset as usize + offset as usize),
+            ))
+        } else {
+            Some(((mapped_size - offset) as usize, None))
+        }
+    }
+
+    /// Converts a relative virtual address to an offset into the file.
+    pub fn rva_to_offset(&self, rva: u32) -> Option<usize> {
+        self.sections.iter().find_map(|section| {
+            let offset = rva.checked_sub(section.virtual_address)?;
+            // the part of the section past its raw data is zero filled and not in the file
+            (offset < section.raw_size).then(|| section.raw_offset as usize + offset as usize)
+        })
+    }
+
+    /// The file version from the version resource, e.g. `1.0.0.412`.
+    pub fn file_version(&self) -> Option<String> {
+        let (rva, _) = self.resource_directory?;
+        let resources = self.rva_to_offset(rva)?;
+
+        // type -> name -> language, taking the first name and language
+        let by_name = self.resource_entry(resources, resources, Some(RT_VERSION))?;
+        let by_language = self.resource_entry(resources, by_name, None)?;
+        let data_entry = self.resource_entry(resources, by_language, None)?;
+
+        let data_rva = u32_at(&self.data, data_entry).ok()?;
+        let data_size = u32_at(&self.data, data_entry + 4).ok()? as usize;
+        let version_info = bytes_at(&self.data, self.rva_to_offset(data_rva)?, data_size).ok()?;
+
+        // VS_FIXEDFILEINFO follows the UTF-16 key, 32 bit aligned
+        let fixed = (0..version_info.len().saturating_sub(16))
+            .step_by(4)
+            .find(|&offset| {
+                u32_at(version_info, offset).ok() == Some(VS_FIXEDFILEINFO_SIGNATURE)
+            })?;
+        let most_significant = u32_at(version_info, fixed + 8).ok()?;
+        let least_significant = u32_at(version_info, fixed + 12).ok()?;
+        Some(format!(
+            "{}.{}.{}.{}",
+            most_significant >> 16,
+            most_significant & 0xFFFF,
+            least_significant >> 16,
+            least_significant & 0xFFFF
+        ))
+    }
+
+    /// Offset of the entry with `id` (or the first entry) of the resource directory
+    /// at `directory`, resolved to the subdirectory or data entry it points to.
+    fn resource_entry(&self, resources: usize, directory: usize, id: Option<u32>) -> Option<usize> {
+        let named = u16_at(&self.data, directory + 12).ok()? as usize;
+        let ids = u16_at(&self.data, directory + 14).ok()? as usize;
+        (0..named + ids).find_map(|index| {
+            let entry = directory + 16 + index * 8;
+            let name = u32_at(&self.data, entry).ok()?;
+            if id.is_some_and(|id| id != name) {
+                return None;
+            }
+            let target = u32_at(&self.data, entry + 4).ok()?;
+            Some(resources + (target & 0x7FFF_FFFF) as usize)
+        })
+    }
+
+    /// SHA-256 of the raw `.text` section.
+    pub fn text_hash(&self) -> Option<String> {
+        let text = self.section(".text")?;
+        let digest = Sha256::digest(self.section_data(text));
+        Some(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+    }
+
+    pub fn client_build(&self) -> ClientBuild {
+        ClientBuild {
+            file_version: self.file_version(),
+            time_date_stamp: self.time_date_stamp,
+            size_of_image: self.size_of_image,
+            sections: self.sections.clone(),
+            text_hash: self.text_hash(),
+        }
+    }
+}
+
+/// Reads the file as the loader would map it at its preferred image base, so
+/// pointer paths can be followed without running the executable.
+impl MemoryReader for PeFile {
+    fn pid(&self) -> u32 {
+        0
+    }
+
+    fn modules(&self) -> Result<Vec<Module>, Error> {
+        let path = self.path.clone().unwrap_or_default();
+        Ok(vec![Module {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            path,
+            base: self.image_base as usize,
+            size: self.size_of_image as usize,
+        }])
+    }
+
+    /// A PE file only contains a single module, returned whatever the name.
+    fn module(&self, _module_name: &str) -> Result<Module, Error> {
+        Ok(self.modules()?.remove(0))
+    }
+
+    /// The headers followed by one region per section.
+    fn regions(&self) -> Result<Vec<MemoryRegion>, Error> {
+        let base = self.image_base as usize;
+        let header_size = self
+            .sections
+            .iter()
+            .map(|section| section.virtual_address as usize)
+            .min()
+            .unwrap_or(self.size_of_image as usize);
+        let header = MemoryRegion {
+            base,
+            size: header_size,
+            protection: Protection {
+                read: true,
+                write: false,
+                execute: false,
+            },
+            kind: RegionKind::Image,
+        };
+
+        let mut regions = vec![header];
+        regions.extend(self.sections.iter().map(|section| MemoryRegion {
+            base: base + section.virtual_address as usize,
+            size: section.virtual_size.max(section.raw_size) as usize,
+            protection: section.protection(),
+            kind: RegionKind::Image,
+        }));
+        regions.sort_by_key(|region| region.base);
+        Ok(regions)
+    }
+
+    fn read_bytes(&self, address: usize, buffer: &mut [u8]) -> Result<(), Error> {
+        let mut filled = 0;
+        while filled < buffer.len() {
+            let current = address + filled;
+            let (available, file_offset) = current
+                .checked_sub(self.image_base as usize)
+                .and_then(|rva| u32::try_from(rva).ok())
+                .and_then(|rva| self.mapping_at(rva))
+                .ok_or(Error::UnreadableAddress { address: current })?;
+
+            let length = available.min(buffer.len() - filled);
+            let target = &mut buffer[filled..filled + length];
+            match file_offset {
+                Some(offset) => target.copy_from_slice(
+                    self.data
+                        .get(offset..offset + length)
+                        .ok_or(Error::UnreadableAddress { address: current })?,
+                ),
+                None => target.fill(0),
+            }
+            filled += length;
+        }
+        Ok(())
+    }
+}
+
+/// What identifies a client build, without keeping the whole file around.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientBuild {
+    pub file_version: Option<String>,
+    pub time_date_stamp: u32,
+    pub size_of_image: u32,
+    pub sections: Vec<PeSection>,
+    pub text_hash: Option<String>,
+}
+
+impl ClientBuild {
+    pub fn load(path: &Path) -> Result<Self, PeError> {
+        Ok(PeFile::load(path)?.client_build())
+    }
+
+    /// The link timestamp as a readable date.
+    pub fn link_time(&self) -> String {
+        NaiveDateTime::from_timestamp_opt(self.time_date_stamp as i64, 0)
+            .map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| self.time_date_stamp.to_string())
+    }
+
+    /// Link timestamp and image size, same format as `scan_cache::client_build_key`.
+    pub fn key(&self) -> String {
+        format!("{:08X}{:x}", self.time_date_stamp, self.size_of_image)
+    }
+}
+
+fn invalid(reason: &str) -> PeError {
+    PeError::InvalidHeader {
+        reason: reason.into(),
+    }
+}
+
+fn bytes_at(data: &[u8], offset: usize, length: usize) -> Result<&[u8], PeError> {
+    data.get(offset..offset + length).ok_or(PeError::Truncated {
+        offset: offset + length,
+    })
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Result<u16, PeError> {
+    Ok(u16::from_le_bytes(
+        bytes_at(data, offset, 2)?.try_into().unwrap(),
+    ))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32, PeError> {
+    Ok(u32::from_le_bytes(
+        bytes_at(data, offset, 4)?.try_into().unwrap(),
+    ))
+}
+
+fn u64_at(data: &[u8], offset: usize) -> Result<u64, PeError> {
+    Ok(u64::from_le_bytes(
+        bytes_at(data, offset, 8)?.try_into().unwrap(),
+    ))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const TEXT_RVA: u32 = 0x1000;
+    const TEXT_OFFSET: usize = 0x400;
+
+    fn put(data: &mut [u8], offset: usize, bytes: &[u8]) {
+        data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    /// A PE32+ image with `.text`, `.data` and `.rsrc` sections and version 1.0.0.412,
+    /// `code` is placed at the start of `.text`.
+    pub(crate) fn sample_pe(code: &[u8]) -> Vec<u8> {
+        let mut data = vec![0u8; 0xA00];
+        put(&mut data, 0, b"MZ");
+        put(&mut data, 0x3C, &0x80u32.to_le_bytes());
+        put(&mut data, 0x80, b"PE\0\0");
+
+        // file header
+        put(&mut data, 0x84, &0x8664u16.to_le_bytes());
+        put(&mut data, 0x86, &3u16.to_le_bytes());
+        put(&mut data, 0x88, &0x5F3A_1C2Bu32.to_le_bytes());
+        put(&mut data, 0x94, &0xF0u16.to_le_bytes());
+
+        // optional header
+        let optional = 0x98;
+        put(&mut data, optional, &0x20Bu16.to_le_bytes());
+        put(&mut data, optional + 24, &0x1_4000_0000u64.to_le_bytes());
+        put(&mut data, optional + 56, &0x4000u32.to_le_bytes());
+        put(&mut data, optional + 108, &16u32.to_le_bytes());
+        put(&mut data, optional + 112 + 16, &0x3000u32.to_le_bytes());
+        put(&mut data, optional + 112 + 20, &0x100u32.to_le_bytes());
+
+        let sections: [(&[u8], u32, u32, u32); 3] = [
+            (b".text", TEXT_RVA, TEXT_OFFSET as u32, 0x6000_0020),
+            (b".data", 0x2000, 0x600, 0xC000_0040),
+            (b".rsrc", 0x3000, 0x800, 0x4000_0040),
+        ];
+        for (index, (name, rva, offset, characteristics)) in sections.iter().enumerate() {
+            let header = optional + 0xF0 + index * 40;
+            put(&mut data, header, name);
+            put(&mut data, header + 8, &0x180u32.to_le_bytes());
+            put(&mut data, header + 12, &rva.to_le_bytes());
+            put(&mut data, header + 16, &0x200u32.to_le_bytes());
+            put(&mut data, header + 20, &offset.to_le_bytes());
+            put(&mut data, header + 36, &characteristics.to_le_bytes());
+        }
+
+        put(&mut data, TEXT_OFFSET, code);
+
+        // resource tree: RT_VERSION -> 1 -> 0x409 -> VS_VERSIONINFO
+        let rsrc = 0x800;
+        put(&mut data, rsrc + 14, &1u16.to_le_bytes());
+        put(&mut data, rsrc + 16, &RT_VERSION.to_le_bytes());
+        put(&mut data, rsrc + 20, &0x8000_0018u32.to_le_bytes());
+        put(&mut data, rsrc + 0x18 + 14, &1u16.to_le_bytes());
+        put(&mut data, rsrc + 0x28, &1u32.to_le_bytes());
+        put(&mut data, rsrc + 0x2C, &0x8000_0030u32.to_le_bytes());
+        put(&mut data, rsrc + 0x30 + 14, &1u16.to_le_bytes());
+        put(&mut data, rsrc + 0x40, &0x409u32.to_le_bytes());
+        put(&mut data, rsrc + 0x44, &0x48u32.to_le_bytes());
+        put(&mut data, rsrc + 0x48, &0x3060u32.to_le_bytes());
+        put(&mut data, rsrc + 0x4C, &92u32.to_le_bytes());
+
+        let version = rsrc + 0x60;
+        put(&mut data, version, &92u16.to_le_bytes());
+        put(&mut data, version + 2, &52u16.to_le_bytes());
+        let key: Vec<u8> = "VS_VERSION_INFO\0"
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect();
+        put(&mut data, version + 6, &key);
+        put(
+            &mut data,
+            version + 40,
+            &VS_FIXEDFILEINFO_SIGNATURE.to_le_bytes(),
+        );
+        put(&mut data, version + 48, &0x0001_0000u32.to_le_bytes());
+        put(&mut data, version + 52, &412u32.to_le_bytes());
+
+        data
+    }
+
+    #[test]
+    fn parses_headers_and_sections() {
+        let pe = PeFile::parse(sample_pe(&[0xC3])).unwrap();
+        assert_eq!(pe.machine, 0x8664);
+        assert_eq!(pe.time_date_stamp, 0x5F3A_1C2B);
+        assert_eq!(pe.image_base, 0x1_4000_0000);
+        assert_eq!(pe.size_of_image, 0x4000);
+
+        let names: Vec<&str> = pe.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, [".text", ".data", ".rsrc"]);
+        let text = pe.section(".text").unwrap();
+        assert!(text.is_executable());
+        assert!(!pe.section(".data").unwrap().is_executable());
+        assert_eq!(pe.section_data(text)[0], 0xC3);
+        assert_eq!(pe.rva_to_offset(0x1010), Some(0x410));
+        assert_eq!(pe.rva_to_offset(0x1300), None);
+    }
+
+    #[test]
+    fn reads_file_version_and_build() {
+        let path = std::env::temp_dir().join(format!("sample_pe_{}.exe", std::process::id()));
+        fs::write(&path, sample_pe(&[0xC3])).unwrap();
+        let build = ClientBuild::load(&path);
+        fs::remove_file(&path).unwrap();
+        let build = build.unwrap();
+
+        assert_eq!(build.file_version.as_deref(), Some("1.0.0.412"));
+        assert_eq!(build.link_time(), "2020-08-17 05:56:59 UTC");
+        assert_eq!(build.key(), "5F3A1C2B4000");
+        assert_eq!(build.text_hash.as_ref().unwrap().len(), 64);
+        assert_ne!(
+            build.text_hash,
+            PeFile::parse(sample_pe(&[0x90])).unwrap().text_hash()
+        );
+    }
+
+    #[test]
+    fn reads_mapped_image() {
+        let pe = PeFile::parse(sample_pe(&[0x48, 0x8B])).unwrap();
+        let base = pe.image_base as usize;
+        assert_eq!(
+            pe.get_module_begin_end("trose.exe"),
+            Some((base, base + 0x4000))
+        );
+
+        let mut header = [0u8; 2];
+        pe.read_bytes(base, &mut header).unwrap();
+        assert_eq!(&header, b"MZ");
+        assert_eq!(pe.read_u8(base + 0x1001).unwrap(), 0x8B);
+        // .text ends at 0x1200, .data follows at 0x2000
+        assert!(pe.read_u32(base + 0x11FE).is_err());
+        assert!(pe.read_u8(base + 0x5000).is_err());
+    }
+
+    #[test]
+    fn rejects_broken_files() {
+        assert!(matches!(
+            PeFile::parse(b"ELF".to_vec()),
+            Err(PeError::InvalidHeader { .. })
+        ));
+        let mut truncated = sample_pe(&[]);
+        truncated.truncate(0x100);
+        assert!(matches!(
+            PeFile::parse(truncated),
+            Err(PeError::Truncated { .. })
+        ));
+        assert!(matches!(
+            ClientBuild::load(Path::new("/nonexistent/trose.exe")),
+            Err(PeError::IOError { .. })
+        ));
+    }
+}
@@ -0,0 +1,396 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::encoding::TextEncoding;
+use crate::error::Error;
+
+/// Strings are read in pieces of this size, most names fit in one.
+const STRING_CHUNK_SIZE: usize = 64;
+
+#[cfg(target_os = "linux")]
+mod linux;
+mod pointer_path;
+#[cfg(windows)]
+mod windows;
+
+#[cfg(target_os = "linux")]
+pub use self::linux::open_process;
+pub use self::pointer_path::{
+    PointerErrorKind, PointerPath, PointerPathError, PointerPathParseError, PointerStep,
+    PointerTrace,
+};
+#[cfg(windows)]
+pub use self::windows::open_process;
+
+/// Decodes the string at the start of `bytes` that was read from `address`, `None`
+/// when its null terminator isn't in `bytes` yet.
+///
+/// Shared by `MemoryReader::read_string` and the inline strings of remote structs.
+pub(crate) fn decode_string(
+    bytes: &[u8],
+    address: usize,
+    encoding: TextEncoding,
+) -> Option<Result<String, Error>> {
+    let length = bytes.iter().position(|&byte| byte == 0)?;
+    Some(
+        encoding
+            .decode(&bytes[..length])
+            .ok_or(Error::InvalidText { address, encoding }),
+    )
+}
+
+/// A module (executable or library) loaded into a process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Module {
+    /// File name, e.g. `trose.exe`.
+    pub name: String,
+    pub path: PathBuf,
+    pub base: usize,
+    pub size: usize,
+}
+
+impl Module {
+    pub fn end(&self) -> usize {
+        self.base + self.size
+    }
+
+    /// Module names are compared like Windows compares file names, ignoring case.
+    pub fn is_named(&self, name: &str) -> bool {
+        self.name.to_lowercase() == name.to_lowercase()
+    }
+}
+
+/// Access rights of a memory region.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Protection {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+impl fmt::Display for Protection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flag = |set: bool, c: char| if set { c } else { '-' };
+        write!(
+            f,
+            "{}{}{}",
+            flag(self.read, 'r'),
+            flag(self.write, 'w'),
+            flag(self.execute, 'x')
+        )
+    }
+}
+
+/// What backs a memory region, named after the `MEM_*` types of `VirtualQueryEx`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionKind {
+    /// Mapped from an executable or library.
+    Image,
+    /// Mapped from any other file or shared memory.
+    Mapped,
+    /// Heap, stacks and other memory that belongs to the process alone.
+    Private,
+}
+
+/// A range of committed memory with the same protection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryRegion {
+    pub base: usize,
+    pub size: usize,
+    pub protection: Protection,
+    pub kind: RegionKind,
+}
+
+impl MemoryRegion {
+    pub fn end(&self) -> usize {
+        self.base + self.size
+    }
+}
+
+macro_rules! define_number_read {
+    ($type: ident, $name: ident, $bytes: expr) => {
+        #[allow(dead_code)]
+        fn $name(&self, address: usize) -> Result<$type, Error> {
+            let mut buffer = [0u8; $bytes];
+            self.read_bytes(address, &mut buffer)?;
+            Ok($type::from_le_bytes(buffer))
+        }
+    };
+}
+
+/// Read access to the memory of a (possibly remote) process.
+///
+/// Implementors only have to provide raw byte reads and the module list, the typed
+/// helpers are built on top of `read_bytes` and the lookups on top of `modules`.
+pub trait MemoryReader {
+    fn pid(&self) -> u32;
+
+    /// Lists the modules loaded into the process.
+    fn modules(&self) -> Result<Vec<Module>, Error>;
+
+    /// Finds the module with the given file name, ignoring case.
+    fn module(&self, module_name: &str) -> Result<Module, Error> {
+        self.modules()?
+            .into_iter()
+            .find(|module| module.is_named(module_name))
+            .ok_or_else(|| Error::ModuleNotFound {
+                pid: self.pid(),
+                name: module_name.into(),
+            })
+    }
+
+    /// Returns the start and end address of the module with the given name.
+    fn get_module_begin_end(&self, module_name: &str) -> Option<(usize, usize)> {
+        self.module(module_name)
+            .ok()
+            .map(|module| (module.base, module.end()))
+    }
+
+    /// Lists the committed memory regions in ascending address order.
+    fn regions(&self) -> Result<Vec<MemoryRegion>, Error>;
+
+    /// Fills `buffer` with the bytes starting at `address`.
+    fn read_bytes(&self, address: usize, buffer: &mut [u8]) -> Result<(), Error>;
+
+    /// Reads a null terminated string of at most `max_length` bytes.
+    fn read_string(
+        &self,
+        address: usize,
+        max_length: usize,
+        encoding: TextEncoding,
+    ) -> Result<String, Error> {
+        let mut bytes = Vec::new();
+        let mut chunk = [0u8; STRING_CHUNK_SIZE];
+
+        // one byte more than the limit, so a string of exactly `max_length` finds its terminator
+        while bytes.len() <= max_length {
+            let current = address + bytes.len();
+            let chunk = &mut chunk[..STRING_CHUNK_SIZE.min(max_length + 1 - bytes.len())];
+            if self.read_bytes(current, chunk).is_err() {
+                // the string may end right before unreadable memory, read up to there
+                for (index, byte) in chunk.iter_mut().enumerate() {
+                    *byte = self.read_u8(current + index)?;
+                    if *byte == 0 {
+                        break;
+                    }
+                }
+            }
+
+            bytes.extend_from_slice(chunk);
+            if let Some(result) = decode_string(&bytes, address, encoding) {
+                return result;
+            }
+        }
+
+        Err(Error::StringTooLong {
+            address,
+            max_length,
+        })
+    }
+
+    fn read_u8(&self, address: usize) -> Result<u8, Error> {
+        let mut buffer = [0u8; 1];
+        self.read_bytes(address, &mut buffer)?;
+        Ok(buffer[0])
+    }
+
+    define_number_read!(u32, read_u32, 4);
+    define_number_read!(u64, read_u64, 8);
+    define_number_read!(u128, read_u128, 16);
+    define_number_read!(i32, read_i32, 4);
+    define_number_read!(i64, read_i64, 8);
+    define_number_read!(f32, read_f32, 4);
+    define_number_read!(f64, read_f64, 8);
+}
+
+/// In-memory process backed by byte buffers mapped at chosen base addresses.
+///
+/// Reads that are not fully covered by a single region fail like an unmapped
+/// page would in a real process.
+#[derive(Debug, Default)]
+pub struct FakeProcess {
+    pub pid: u32,
+    regions: Vec<(MemoryRegion, Vec<u8>)>,
+    modules: Vec<Module>,
+}
+
+impl FakeProcess {
+    pub fn new(pid: u32) -> Self {
+        FakeProcess {
+            pid,
+            ..Default::default()
+        }
+    }
+
+    /// Maps `bytes` at `base` as private read/write memory.
+    pub fn with_region(self, base: usize, bytes: Vec<u8>) -> Self {
+        let protection = Protection {
+            read: true,
+            write: true,
+            execute: false,
+        };
+        self.with_protected_region(base, bytes, protection, RegionKind::Private)
+    }
+
+    /// Maps `bytes` at `base` with the given protection and kind.
+    pub fn with_protected_region(
+        mut self,
+        base: usize,
+        bytes: Vec<u8>,
+        protection: Protection,
+        kind: RegionKind,
+    ) -> Self {
+        let region = MemoryRegion {
+            base,
+            size: bytes.len(),
+            protection,
+            kind,
+        };
+        self.regions.push((region, bytes));
+        self
+    }
+
+    /// Maps `bytes` at `base` and registers them as module `name`.
+    pub fn with_module(mut self, name: &str, base: usize, bytes: Vec<u8>) -> Self {
+        self.modules.push(Module {
+            name: name.into(),
+            path: PathBuf::from(r"C:\ROSE Online").join(name),
+            base,
+            size: bytes.len(),
+        });
+        let protection = Protection {
+            read: true,
+            write: false,
+            execute: true,
+        };
+        self.with_protected_region(base, bytes, protection, RegionKind::Image)
+    }
+}
+
+impl MemoryReader for FakeProcess {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    fn modules(&self) -> Result<Vec<Module>, Error> {
+        Ok(self.modules.clone())
+    }
+
+    fn regions(&self) -> Result<Vec<MemoryRegion>, Error> {
+        let mut regions: Vec<MemoryRegion> = self
+            .regions
+            .iter()
+            .map(|(region, _)| region.clone())
+            .collect();
+        regions.sort_by_key(|region| region.base);
+        Ok(regions)
+    }
+
+    fn read_bytes(&self, address: usize, buffer: &mut [u8]) -> Result<(), Error> {
+        let region = self
+            .regions
+            .iter()
+            .find(|(region, _)| address >= region.base && address + buffer.len() <= region.end());
+
+        match region {
+            Some((region, bytes)) => {
+                let offset = address - region.base;
+                buffer.copy_from_slice(&bytes[offset..offset + buffer.len()]);
+                Ok(())
+            }
+            None => Err(Error::UnreadableAddress { address }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_modules_by_exact_name_ignoring_case() {
+        let process = FakeProcess::new(7)
+            .with_module("TRose.exe", 0x1_4000_0000, vec![0; 0x100])
+            .with_module("trose.exe.dll", 0x1_8000_0000, vec![0; 0x10]);
+
+        let module = process.module("trose.exe").unwrap();
+        assert_eq!(module.base, 0x1_4000_0000);
+        assert_eq!(module.end(), 0x1_4000_0100);
+        assert_eq!(
+            process.get_module_begin_end("TROSE.EXE.DLL"),
+            Some((0x1_8000_0000, 0x1_8000_0010))
+        );
+        // a prefix of a longer name is no match
+        assert!(process.module("trose").is_err());
+    }
+
+    #[test]
+    fn reports_missing_modules() {
+        let process = FakeProcess::new(7).with_module("trose.exe", 0x1000, vec![0; 0x10]);
+        let error = process.module("ntdll.dll").unwrap_err();
+        assert!(matches!(error, Error::ModuleNotFound { pid: 7, .. }));
+        assert_eq!(
+            error.to_string(),
+            "module ntdll.dll is not loaded in process 7"
+        );
+    }
+
+    #[test]
+    fn reads_strings_in_chunks() {
+        let mut memory = vec![b'a'; 0x100];
+        memory[0x90] = 0;
+        memory[0xF0..0xF5].copy_from_slice(b"Xik\0\0");
+        // the region ends right after the terminator
+        let process = FakeProcess::new(1)
+            .with_region(0x1000, memory)
+            .with_region(0x2000, b"Xikeon\0".to_vec());
+
+        let long = process
+            .read_string(0x1000, 0x100, TextEncoding::Utf8)
+            .unwrap();
+        assert_eq!(long.len(), 0x90);
+        assert_eq!(
+            process
+                .read_string(0x10F0, 0x10, TextEncoding::Utf8)
+                .unwrap(),
+            "Xik"
+        );
+        assert_eq!(
+            process.read_string(0x2000, 6, TextEncoding::Utf8).unwrap(),
+            "Xikeon"
+        );
+    }
+
+    #[test]
+    fn rejects_unterminated_and_invalid_strings() {
+        let process = FakeProcess::new(1)
+            .with_region(0x1000, vec![b'a'; 0x100])
+            .with_region(0x2000, b"Ren\xE9\0".to_vec())
+            .with_region(0x3000, b"Xikeon".to_vec());
+
+        assert!(matches!(
+            process.read_string(0x1000, 0x20, TextEncoding::Utf8),
+            Err(Error::StringTooLong {
+                address: 0x1000,
+                max_length: 0x20
+            })
+        ));
+        assert!(matches!(
+            process.read_string(0x2000, 0x20, TextEncoding::Utf8),
+            Err(Error::InvalidText {
+                encoding: TextEncoding::Utf8,
+                ..
+            })
+        ));
+        assert_eq!(
+            process
+                .read_string(0x2000, 0x20, TextEncoding::Cp1252)
+                .unwrap(),
+            "René"
+        );
+        assert!(matches!(
+            process.read_string(0x3000, 0x20, TextEncoding::Utf8),
+            Err(Error::UnreadableAddress { address: 0x3006 })
+        ));
+    }
+}
@@ -0,0 +1,325 @@
+// Reads the memory of Wine/Proton hosted processes through procfs.
+//
+// Wine maps PE images from their file on disk, so the image range of a module
+// shows up in /proc/<pid>/maps under the path of the .exe/.dll.
+
+use std::{
+    fs::{self, File},
+    io::{Error as IoError, ErrorKind},
+    os::unix::fs::FileExt,
+    path::Path,
+};
+
+use super::{MemoryReader, MemoryRegion, Module, Protection, RegionKind};
+use crate::error::Error;
+
+/// Opens process with specified id.
+pub fn open_process(pid: u32) -> Result<LinuxProcess, Error> {
+    let mem = File::open(format!("/proc/{}/mem", pid))
+        .map_err(|io_error| procfs_error(pid, "failed to open the process memory", io_error))?;
+    Ok(LinuxProcess { pid, mem })
+}
+
+/// The procfs directory of a process disappears when it exits and can only be
+/// read by users allowed to ptrace it.
+fn procfs_error(pid: u32, context: &str, io_error: IoError) -> Error {
+    match io_error.kind() {
+        ErrorKind::NotFound => Error::ProcessExited { pid },
+        ErrorKind::PermissionDenied => Error::AccessDenied { pid },
+        _ => Error::IOError {
+            context: format!("{} of process {}", context, pid),
+            io_error,
+        },
+    }
+}
+
+#[derive(Debug)]
+pub struct LinuxProcess {
+    pub pid: u32,
+    mem: File,
+}
+
+impl LinuxProcess {
+    fn maps(&self) -> Result<String, Error> {
+        fs::read_to_string(format!("/proc/{}/maps", self.pid))
+            .map_err(|io_error| procfs_error(self.pid, "failed to read the memory map", io_error))
+    }
+}
+
+/// A single line of /proc/<pid>/maps.
+#[derive(Debug, PartialEq)]
+pub struct MapsEntry {
+    pub begin: usize,
+    pub end: usize,
+    pub permissions: String,
+    pub offset: usize,
+    pub path: Option<String>,
+}
+
+pub fn parse_maps(maps: &str) -> Vec<MapsEntry> {
+    maps.lines().filter_map(parse_maps_line).collect()
+}
+
+fn parse_maps_line(line: &str) -> Option<MapsEntry> {
+    // begin-end perms offset dev inode [path]
+    let mut fields = line.splitn(6, ' ');
+    let (begin, end) = fields.next()?.split_once('-')?;
+    let permissions = fields.next()?.to_string();
+    let offset = usize::from_str_radix(fields.next()?, 16).ok()?;
+    let _dev = fields.next()?;
+    let _inode = fields.next()?;
+    let path = fields
+        .next()
+        .map(|path| path.trim_start().to_string())
+        .filter(|path| !path.is_empty());
+
+    Some(MapsEntry {
+        begin: usize::from_str_radix(begin, 16).ok()?,
+        end: usize::from_str_radix(end, 16).ok()?,
+        permissions,
+        offset,
+        path,
+    })
+}
+
+impl MapsEntry {
+    pub fn protection(&self) -> Protection {
+        let flag =
+            |index: usize, expected: u8| self.permissions.as_bytes().get(index) == Some(&expected);
+        Protection {
+            read: flag(0, b'r'),
+            write: flag(1, b'w'),
+            execute: flag(2, b'x'),
+        }
+    }
+
+    fn is_file(&self) -> bool {
+        self.path
+            .as_deref()
+            .is_some_and(|path| path.starts_with('/'))
+    }
+
+    fn is_shared(&self) -> bool {
+        self.permissions.as_bytes().get(3) == Some(&b's')
+    }
+}
+
+/// Converts the mappings into regions.
+///
+/// procfs doesn't tell images apart from other mapped files, files with an
+/// executable mapping are taken for images.
+pub fn maps_regions(entries: &[MapsEntry]) -> Vec<MemoryRegion> {
+    let images: Vec<&str> = entries
+        .iter()
+        .filter(|entry| entry.is_file() && entry.protection().execute)
+        .filter_map(|entry| entry.path.as_deref())
+        .collect();
+
+    entries
+        .iter()
+        .map(|entry| {
+            let kind = if entry.is_file() {
+                if images.contains(&entry.path.as_deref().unwrap_or_default()) {
+                    RegionKind::Image
+                } else {
+                    RegionKind::Mapped
+                }
+            } else if entry.is_shared() {
+                RegionKind::Mapped
+            } else {
+                RegionKind::Private
+            };
+            MemoryRegion {
+                base: entry.begin,
+                size: entry.end - entry.begin,
+                protection: entry.protection(),
+                kind,
+            }
+        })
+        .collect()
+}
+
+/// Groups the file backed mappings into modules, each spanning all mappings of its file.
+///
+/// Pseudo paths like `[heap]` are skipped, they aren't files.
+pub fn maps_modules(entries: &[MapsEntry]) -> Vec<Module> {
+    let mut modules: Vec<Module> = vec![];
+    for entry in entries {
+        let path = match entry.path.as_deref() {
+            Some(path) if path.starts_with('/') => Path::new(path),
+            _ => continue,
+        };
+        let name = match path.file_name() {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => continue,
+        };
+
+        match modules.iter_mut().find(|module| module.path == path) {
+            Some(module) => {
+                let end = module.end().max(entry.end);
+                module.base = module.base.min(entry.begin);
+                module.size = end - module.base;
+            }
+            None => modules.push(Module {
+                name,
+                path: path.into(),
+                base: entry.begin,
+                size: entry.end - entry.begin,
+            }),
+        }
+    }
+    modules
+}
+
+impl MemoryReader for LinuxProcess {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    fn modules(&self) -> Result<Vec<Module>, Error> {
+        Ok(maps_modules(&parse_maps(&self.maps()?)))
+    }
+
+    fn regions(&self) -> Result<Vec<MemoryRegion>, Error> {
+        Ok(maps_regions(&parse_maps(&self.maps()?)))
+    }
+
+    fn read_bytes(&self, address: usize, buffer: &mut [u8]) -> Result<(), Error> {
+        let mut read: usize = 0;
+        while read < buffer.len() {
+            match self
+                .mem
+                .read_at(&mut buffer[read..], (address + read) as u64)
+            {
+                // the memory file of an exited process reads as empty
+                Ok(0) if read == 0 => return Err(Error::ProcessExited { pid: self.pid }),
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(_) if read > 0 => break,
+                Err(_) => return Err(Error::UnreadableAddress { address }),
+            }
+        }
+
+        if read != buffer.len() {
+            return Err(Error::PartialRead {
+                address,
+                expected: buffer.len(),
+                actual: read,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAPS: &str = "\
+00110000-00111000 r--p 00000000 00:00 0
+140000000-140001000 r--p 00000000 103:02 1835342                   /home/me/.wine/drive_c/ROSE Online/TRose.exe
+140001000-141c2f000 r-xp 00001000 103:02 1835342                   /home/me/.wine/drive_c/ROSE Online/TRose.exe
+141c2f000-142000000 rw-p 01c2f000 103:02 1835342                   /home/me/.wine/drive_c/ROSE Online/TRose.exe
+7f0000000000-7f0000021000 rw-p 00000000 00:00 0                    [heap]
+7f0000100000-7f0000180000 r-xp 00000000 103:02 1835400             /usr/lib/wine/x86_64-unix/ntdll.so
+";
+
+    #[test]
+    fn parses_maps_lines() {
+        let entries = parse_maps(MAPS);
+        assert_eq!(entries.len(), 6);
+        assert_eq!(entries[0].path, None);
+        assert_eq!(
+            entries[2],
+            MapsEntry {
+                begin: 0x140001000,
+                end: 0x141c2f000,
+                permissions: "r-xp".into(),
+                offset: 0x1000,
+                path: Some("/home/me/.wine/drive_c/ROSE Online/TRose.exe".into()),
+            }
+        );
+        assert_eq!(entries[4].path.as_deref(), Some("[heap]"));
+    }
+
+    #[test]
+    fn module_spans_all_image_mappings() {
+        let modules = maps_modules(&parse_maps(MAPS));
+        assert_eq!(modules.len(), 2);
+        assert_eq!(
+            modules[0],
+            Module {
+                name: "TRose.exe".into(),
+                path: "/home/me/.wine/drive_c/ROSE Online/TRose.exe".into(),
+                base: 0x140000000,
+                size: 0x2000000,
+            }
+        );
+        assert!(modules[0].is_named("trose.exe"));
+        assert_eq!(modules[1].name, "ntdll.so");
+    }
+
+    #[test]
+    fn classifies_regions() {
+        let regions = maps_regions(&parse_maps(MAPS));
+        assert_eq!(regions.len(), 6);
+        assert_eq!(
+            regions[0],
+            MemoryRegion {
+                base: 0x110000,
+                size: 0x1000,
+                protection: Protection {
+                    read: true,
+                    write: false,
+                    execute: false,
+                },
+                kind: RegionKind::Private,
+            }
+        );
+        assert_eq!(regions[1].kind, RegionKind::Image);
+        assert_eq!(regions[2].protection.to_string(), "r-x");
+        assert_eq!(regions[3].protection.to_string(), "rw-");
+        assert_eq!(regions[4].kind, RegionKind::Private);
+        assert_eq!(regions[5].kind, RegionKind::Image);
+    }
+
+    #[test]
+    fn lists_own_modules() {
+        let process = open_process(std::process::id()).unwrap();
+        let modules = process.modules().unwrap();
+        let exe = std::env::current_exe().unwrap();
+        let name = exe.file_name().unwrap().to_str().unwrap();
+        assert!(process.module(name).is_ok());
+        assert!(modules.iter().all(|module| module.size > 0));
+        assert!(matches!(
+            process.module("trose.exe"),
+            Err(Error::ModuleNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn reports_exited_processes() {
+        // pids are below 2^22 on Linux
+        assert!(matches!(
+            open_process(u32::MAX),
+            Err(Error::ProcessExited { pid: u32::MAX })
+        ));
+        let process = open_process(std::process::id()).unwrap();
+        assert!(matches!(
+            process.read_u8(0),
+            Err(Error::UnreadableAddress { address: 0 })
+        ));
+    }
+
+    #[test]
+    fn reads_own_memory() {
+        let value: u64 = 0x1122_3344_5566_7788;
+        let process = open_process(std::process::id()).unwrap();
+        assert_eq!(
+            process.read_u64(&value as *const u64 as usize).unwrap(),
+            value
+        );
+    }
+}
@@ -0,0 +1,508 @@
+// A pointer chain written as text, e.g.
+//
+//   sig("? 83 EC 28 ? 8B 05") -> rip_rel(7, 11) -> deref64 -> +0xB10
+//
+// Every step takes the address produced by the previous step, the first step
+// has to produce an address on its own (only `sig` can do that).
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::MemoryReader;
+use crate::error::Error;
+use crate::helpers::sig_scan;
+use crate::signature::ScanTarget;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PointerStep {
+    /// Address of the first match of the signature in the module.
+    Sig(String),
+    /// Target of the RIP-relative operand of the instruction at the address.
+    RipRel {
+        displacement_offset: usize,
+        instruction_length: usize,
+    },
+    Deref64,
+    Deref32,
+    Offset(isize),
+}
+
+impl fmt::Display for PointerStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PointerStep::Sig(signature) => write!(f, "sig(\"{}\")", signature),
+            PointerStep::RipRel {
+                displacement_offset,
+                instruction_length,
+            } => write!(
+                f,
+                "rip_rel({}, {})",
+                displacement_offset, instruction_length
+            ),
+            PointerStep::Deref64 => write!(f, "deref64"),
+            PointerStep::Deref32 => write!(f, "deref32"),
+            PointerStep::Offset(offset) if *offset < 0 => {
+                write!(f, "-{:#x}", offset.unsigned_abs())
+            }
+            PointerStep::Offset(offset) => write!(f, "+{:#x}", offset),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PointerPath {
+    pub steps: Vec<PointerStep>,
+}
+
+impl fmt::Display for PointerPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let steps: Vec<String> = self.steps.iter().map(|step| step.to_string()).collect();
+        write!(f, "{}", steps.join(" -> "))
+    }
+}
+
+#[derive(Debug)]
+pub enum PointerErrorKind {
+    NoStartAddress,
+    SignatureNotFound,
+    NullPointer {
+        address: usize,
+    },
+    AddressOverflow,
+    /// The module couldn't be found or memory couldn't be read.
+    Error(Error),
+}
+
+/// The step of a pointer path that failed and why.
+#[derive(Debug)]
+pub struct PointerPathError {
+    pub step: usize,
+    pub step_text: String,
+    pub kind: PointerErrorKind,
+}
+
+impl fmt::Display for PointerPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "step {} ({}): ", self.step + 1, self.step_text)?;
+        match &self.kind {
+            PointerErrorKind::NoStartAddress => write!(f, "no address to start from"),
+            PointerErrorKind::SignatureNotFound => write!(f, "signature not found"),
+            PointerErrorKind::NullPointer { address } => {
+                write!(f, "null pointer at {:#x}", address)
+            }
+            PointerErrorKind::AddressOverflow => write!(f, "address out of range"),
+            PointerErrorKind::Error(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for PointerPathError {}
+
+/// The address produced by every step that succeeded, plus the error of the step that didn't.
+#[derive(Debug)]
+pub struct PointerTrace {
+    pub addresses: Vec<usize>,
+    pub error: Option<PointerPathError>,
+}
+
+impl PointerPath {
+    pub fn new(steps: Vec<PointerStep>) -> Self {
+        PointerPath { steps }
+    }
+
+    /// Appends a step, returning the extended path.
+    pub fn then(&self, step: PointerStep) -> Self {
+        let mut steps = self.steps.clone();
+        steps.push(step);
+        PointerPath { steps }
+    }
+
+    /// Resolves the whole path, scanning `module` for signatures.
+    pub fn resolve(
+        &self,
+        process: &impl MemoryReader,
+        module: &str,
+    ) -> Result<usize, PointerPathError> {
+        self.trace(process, module).into_result()
+    }
+
+    /// Resolves the steps after `step`, using `address` as the result of `step`.
+    ///
+    /// Used to skip the signature scan when its address is already known.
+    pub fn resolve_after(
+        &self,
+        process: &impl MemoryReader,
+        step: usize,
+        address: usize,
+    ) -> Result<usize, PointerPathError> {
+        self.trace_after(process, step, address).into_result()
+    }
+
+    /// Like `resolve_after`, keeping the address of every step including `step`.
+    pub fn trace_after(
+        &self,
+        process: &impl MemoryReader,
+        step: usize,
+        address: usize,
+    ) -> PointerTrace {
+        let mut trace = self.run(process, "", step + 1, Some(address));
+        trace.addresses.insert(0, address);
+        trace
+    }
+
+    pub fn trace(&self, process: &impl MemoryReader, module: &str) -> PointerTrace {
+        self.run(process, module, 0, None)
+    }
+
+    fn run(
+        &self,
+        process: &impl MemoryReader,
+        module: &str,
+        first_step: usize,
+        start: Option<usize>,
+    ) -> PointerTrace {
+        let mut addresses = vec![];
+        let mut current = start;
+
+        for (index, step) in self.steps.iter().enumerate().skip(first_step) {
+            match resolve_step(process, module, step, current) {
+                Ok(address) => {
+                    addresses.push(address);
+                    current = Some(address);
+                }
+                Err(kind) => {
+                    return PointerTrace {
+                        addresses,
+                        error: Some(PointerPathError {
+                            step: index,
+                            step_text: step.to_string(),
+                            kind,
+                        }),
+                    }
+                }
+            }
+        }
+
+        if current.is_none() {
+            return PointerTrace {
+                addresses,
+                error: Some(PointerPathError {
+                    step: first_step,
+                    step_text: "".into(),
+                    kind: PointerErrorKind::NoStartAddress,
+                }),
+            };
+        }
+
+        PointerTrace {
+            addresses,
+            error: None,
+        }
+    }
+}
+
+impl PointerTrace {
+    pub fn into_result(self) -> Result<usize, PointerPathError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(*self.addresses.last().unwrap_or(&0)),
+        }
+    }
+}
+
+fn resolve_step(
+    process: &impl MemoryReader,
+    module: &str,
+    step: &PointerStep,
+    current: Option<usize>,
+) -> Result<usize, PointerErrorKind> {
+    if let PointerStep::Sig(signature) = step {
+        let module = process.module(module).map_err(PointerErrorKind::Error)?;
+        let target = ScanTarget::Range {
+            begin: module.base,
+            end: module.end(),
+        };
+        return sig_scan(process, signature, &target)
+            .map_err(PointerErrorKind::Error)?
+            .ok_or(PointerErrorKind::SignatureNotFound);
+    }
+
+    let address = current.ok_or(PointerErrorKind::NoStartAddress)?;
+    match step {
+        PointerStep::Sig(_) => unreachable!(),
+        PointerStep::RipRel {
+            displacement_offset,
+            instruction_length,
+        } => {
+            // the address can be any value an earlier deref read
+            let displacement_address = address
+                .checked_add(*displacement_offset)
+                .ok_or(PointerErrorKind::AddressOverflow)?;
+            let displacement = process
+                .read_i32(displacement_address)
+                .map_err(PointerErrorKind::Error)?;
+            address
+                .checked_add(*instruction_length)
+                .and_then(|next| next.checked_add_signed(displacement as isize))
+                .ok_or(PointerErrorKind::AddressOverflow)
+        }
+        PointerStep::Deref64 => {
+            let pointer = process.read_u64(address).map_err(PointerErrorKind::Error)? as usize;
+            non_null(pointer, address)
+        }
+        PointerStep::Deref32 => {
+            let pointer = process.read_u32(address).map_err(PointerErrorKind::Error)? as usize;
+            non_null(pointer, address)
+        }
+        PointerStep::Offset(offset) => address
+            .checked_add_signed(*offset)
+            .ok_or(PointerErrorKind::AddressOverflow),
+    }
+}
+
+fn non_null(pointer: usize, address: usize) -> Result<usize, PointerErrorKind> {
+    if pointer == 0 {
+        return Err(PointerErrorKind::NullPointer { address });
+    }
+    Ok(pointer)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct PointerPathParseError {
+    /// Byte offset into the parsed text.
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for PointerPathParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for PointerPathParseError {}
+
+impl FromStr for PointerPath {
+    type Err = PointerPathParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut steps = vec![];
+        let mut position = 0;
+
+        for part in text.split("->") {
+            let trimmed = part.trim();
+            let step_position = position + (part.len() - part.trim_start().len());
+            steps.push(
+                parse_step(trimmed).map_err(|message| PointerPathParseError {
+                    position: step_position,
+                    message,
+                })?,
+            );
+            position += part.len() + "->".len();
+        }
+
+        Ok(PointerPath { steps })
+    }
+}
+
+fn parse_step(text: &str) -> Result<PointerStep, String> {
+    if text.is_empty() {
+        return Err("expected a step".into());
+    }
+    if let Some(offset) = text.strip_prefix('+') {
+        let offset = parse_number(offset.trim())?;
+        return isize::try_from(offset)
+            .map(PointerStep::Offset)
+            .map_err(|_| "offset out of range".into());
+    }
+    if let Some(offset) = text.strip_prefix('-') {
+        let offset = parse_number(offset.trim())?;
+        return isize::try_from(offset)
+            .ok()
+            .and_then(isize::checked_neg)
+            .map(PointerStep::Offset)
+            .ok_or_else(|| "offset out of range".into());
+    }
+
+    match text {
+        "deref64" => return Ok(PointerStep::Deref64),
+        "deref32" => return Ok(PointerStep::Deref32),
+        _ => {}
+    }
+
+    let (name, arguments) = text
+        .split_once('(')
+        .and_then(|(name, rest)| Some((name.trim(), rest.strip_suffix(')')?)))
+        .ok_or_else(|| format!("unknown step \"{}\"", text))?;
+
+    match name {
+        "sig" => {
+            let signature = arguments
+                .trim()
+                .strip_prefix('"')
+                .and_then(|rest| rest.strip_suffix('"'))
+                .ok_or("sig expects a quoted signature")?;
+            if signature.trim().is_empty() {
+                return Err("sig expects a non-empty signature".into());
+            }
+            Ok(PointerStep::Sig(signature.into()))
+        }
+        "rip_rel" => {
+            let arguments: Vec<&str> = arguments.split(',').map(|a| a.trim()).collect();
+            if arguments.len() != 2 {
+                return Err(
+                    "rip_rel expects a displacement offset and an instruction length".into(),
+                );
+            }
+            Ok(PointerStep::RipRel {
+                displacement_offset: parse_number(arguments[0])?,
+                instruction_length: parse_number(arguments[1])?,
+            })
+        }
+        _ => Err(format!("unknown step \"{}\"", name)),
+    }
+}
+
+fn parse_number(text: &str) -> Result<usize, String> {
+    let result = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+    result.map_err(|_| format!("invalid number \"{}\"", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process_memory::FakeProcess;
+
+    const MODULE_BASE: usize = 0x1_4000_0000;
+
+    fn process() -> FakeProcess {
+        // mov rax, [rip + 0x100] at 0x10, the pointer it loads lives at 0x10 + 7 + 0x100
+        let mut module = vec![0u8; 0x1000];
+        module[0x10..0x17].copy_from_slice(&[0x48, 0x8B, 0x05, 0x00, 0x01, 0x00, 0x00]);
+        module[0x117..0x11F].copy_from_slice(&0x2_0000_0000u64.to_le_bytes());
+
+        let mut player = vec![0u8; 0x100];
+        player[0x20..0x24].copy_from_slice(&0x3000_0000u32.to_le_bytes());
+        // a garbage pointer close to the end of the address space
+        player[0x30..0x38].copy_from_slice(&(usize::MAX as u64 - 2).to_le_bytes());
+
+        FakeProcess::new(1)
+            .with_module("trose.exe", MODULE_BASE, module)
+            .with_region(0x2_0000_0000, player)
+    }
+
+    #[test]
+    fn parses_and_prints_paths() {
+        let path: PointerPath = "sig(\"? 8B 05\") -> rip_rel(3, 7) -> deref64 -> +0xB10 -> -16"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            path.steps,
+            vec![
+                PointerStep::Sig("? 8B 05".into()),
+                PointerStep::RipRel {
+                    displacement_offset: 3,
+                    instruction_length: 7
+                },
+                PointerStep::Deref64,
+                PointerStep::Offset(0xB10),
+                PointerStep::Offset(-16),
+            ]
+        );
+        assert_eq!(
+            path.to_string(),
+            "sig(\"? 8B 05\") -> rip_rel(3, 7) -> deref64 -> +0xb10 -> -0x10"
+        );
+        assert_eq!(path.to_string().parse::<PointerPath>().unwrap(), path);
+    }
+
+    #[test]
+    fn reports_parse_error_position() {
+        let error = "deref64 ->  deref16".parse::<PointerPath>().unwrap_err();
+        assert_eq!(error.position, 12);
+        assert_eq!(error.message, "unknown step \"deref16\"");
+
+        let error = "rip_rel(7) -> deref64".parse::<PointerPath>().unwrap_err();
+        assert_eq!(error.position, 0);
+
+        // offsets that don't fit an isize aren't wrapped
+        for text in [
+            "deref64 -> +0xFFFFFFFFFFFFFFFF",
+            "deref64 -> -0x8000000000000000",
+        ] {
+            let error = text.parse::<PointerPath>().unwrap_err();
+            assert_eq!(error.position, 11);
+            assert_eq!(error.message, "offset out of range");
+        }
+    }
+
+    #[test]
+    fn resolves_steps_in_order() {
+        let process = process();
+        let path: PointerPath = "sig(\"? 8B 05\") -> rip_rel(3, 7) -> deref64 -> +0x20 -> deref32"
+            .parse()
+            .unwrap();
+
+        let trace = path.trace(&process, "trose.exe");
+        assert!(trace.error.is_none());
+        assert_eq!(
+            trace.addresses,
+            vec![
+                MODULE_BASE + 0x10,
+                MODULE_BASE + 0x117,
+                0x2_0000_0000,
+                0x2_0000_0020,
+                0x3000_0000,
+            ]
+        );
+
+        assert_eq!(
+            path.resolve_after(&process, 0, MODULE_BASE + 0x10).unwrap(),
+            0x3000_0000
+        );
+    }
+
+    #[test]
+    fn reports_failing_step() {
+        let process = process();
+        let path: PointerPath = "sig(\"? 8B 05\") -> rip_rel(3, 7) -> deref64 -> +0x40 -> deref64"
+            .parse()
+            .unwrap();
+
+        let error = path.resolve(&process, "trose.exe").unwrap_err();
+        assert_eq!(error.step, 4);
+        assert!(matches!(
+            error.kind,
+            PointerErrorKind::NullPointer {
+                address: 0x2_0000_0040
+            }
+        ));
+        assert_eq!(
+            error.to_string(),
+            "step 5 (deref64): null pointer at 0x200000040"
+        );
+
+        let error = path.resolve(&process, "other.dll").unwrap_err();
+        assert_eq!(error.step, 0);
+        assert!(matches!(
+            error.kind,
+            PointerErrorKind::Error(Error::ModuleNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn reports_overflowing_rip_rel() {
+        let process = process();
+        let path: PointerPath =
+            "sig(\"? 8B 05\") -> rip_rel(3, 7) -> deref64 -> +0x30 -> deref64 -> rip_rel(3, 7)"
+                .parse()
+                .unwrap();
+
+        let error = path.resolve(&process, "trose.exe").unwrap_err();
+        assert_eq!(error.step, 5);
+        assert!(matches!(error.kind, PointerErrorKind::AddressOverflow));
+    }
+}
@@ -0,0 +1,227 @@
+// Modified and simplied version of https://crates.io/crates/process-memory-reader
+
+//! ```no_run
+//! use rose_title_core::process_memory::{self, MemoryReader};
+//!
+//! let process = process_memory::open_process(22212).unwrap();
+//! let module = process.module("Notepad.exe").unwrap();
+//!
+//! process.read_u8(module.base + 0x127).unwrap();
+//! ```
+
+use std::{
+    ffi::OsString, io::Error as IoError, mem::size_of, os::windows::prelude::OsStringExt,
+    path::PathBuf,
+};
+use winapi::ctypes::c_void;
+use winapi::shared::minwindef::{DWORD, TRUE};
+use winapi::shared::winerror::{ERROR_ACCESS_DENIED, ERROR_INVALID_PARAMETER};
+use winapi::um::{
+    handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
+    memoryapi::{ReadProcessMemory, VirtualQueryEx},
+    processthreadsapi::{GetExitCodeProcess, OpenProcess},
+    tlhelp32::{
+        CreateToolhelp32Snapshot, Module32FirstW, Module32NextW, MODULEENTRY32W, TH32CS_SNAPMODULE,
+        TH32CS_SNAPMODULE32,
+    },
+    winnt::{
+        MEMORY_BASIC_INFORMATION, MEM_COMMIT, MEM_IMAGE, MEM_MAPPED, PAGE_EXECUTE,
+        PAGE_EXECUTE_READ, PAGE_EXECUTE_READWRITE, PAGE_EXECUTE_WRITECOPY, PAGE_GUARD,
+        PAGE_NOACCESS, PAGE_READONLY, PAGE_READWRITE, PAGE_WRITECOPY, PROCESS_QUERY_INFORMATION,
+        PROCESS_VM_READ,
+    },
+};
+
+use super::{MemoryReader, MemoryRegion, Module, Protection, RegionKind};
+use crate::error::Error;
+
+/// Exit code `GetExitCodeProcess` reports for running processes.
+const STILL_ACTIVE: DWORD = 259;
+
+/// Opens process with specified id.
+pub fn open_process(pid: u32) -> Result<WindowsProcess, Error> {
+    let handle = unsafe { OpenProcess(PROCESS_VM_READ | PROCESS_QUERY_INFORMATION, 0, pid) };
+    if handle.is_null() {
+        let io_error = IoError::last_os_error();
+        return Err(match io_error.raw_os_error().map(|code| code as DWORD) {
+            Some(ERROR_ACCESS_DENIED) => Error::AccessDenied { pid },
+            // the pid doesn't belong to any process (anymore)
+            Some(ERROR_INVALID_PARAMETER) => Error::ProcessExited { pid },
+            _ => Error::IOError {
+                context: format!("failed to open process {}", pid),
+                io_error,
+            },
+        });
+    }
+    Ok(WindowsProcess { pid, handle })
+}
+
+#[derive(Debug)]
+pub struct WindowsProcess {
+    pub pid: u32,
+    pub handle: *mut c_void,
+}
+
+impl WindowsProcess {
+    fn is_running(&self) -> bool {
+        let mut exit_code: DWORD = 0;
+        let result = unsafe { GetExitCodeProcess(self.handle, &mut exit_code) };
+        result == TRUE && exit_code == STILL_ACTIVE
+    }
+}
+
+impl MemoryReader for WindowsProcess {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    fn modules(&self) -> Result<Vec<Module>, Error> {
+        let handle =
+            unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPMODULE | TH32CS_SNAPMODULE32, self.pid) };
+
+        if handle == INVALID_HANDLE_VALUE {
+            if !self.is_running() {
+                return Err(Error::ProcessExited { pid: self.pid });
+            }
+            return Err(Error::IOError {
+                context: format!("failed to list the modules of process {}", self.pid),
+                io_error: IoError::last_os_error(),
+            });
+        }
+
+        let mut module_entry = MODULEENTRY32W::default();
+        module_entry.dwSize = size_of::<MODULEENTRY32W>() as u32;
+
+        let mut modules = Vec::new();
+        let mut result = unsafe { Module32FirstW(handle, &mut module_entry) };
+        while result == TRUE {
+            modules.push(Module {
+                name: wide_to_string(&module_entry.szModule),
+                path: PathBuf::from(wide_to_string(&module_entry.szExePath)),
+                base: module_entry.modBaseAddr as usize,
+                size: module_entry.modBaseSize as usize,
+            });
+            result = unsafe { Module32NextW(handle, &mut module_entry) };
+        }
+
+        unsafe { CloseHandle(handle) };
+        Ok(modules)
+    }
+
+    fn regions(&self) -> Result<Vec<MemoryRegion>, Error> {
+        let mut regions = Vec::new();
+        let mut info = MEMORY_BASIC_INFORMATION::default();
+        let mut address: usize = 0;
+
+        loop {
+            let written = unsafe {
+                VirtualQueryEx(
+                    self.handle,
+                    address as *const _,
+                    &mut info,
+                    size_of::<MEMORY_BASIC_INFORMATION>(),
+                )
+            };
+            if written == 0 {
+                // fails past the highest user mode address, only an error if nothing was listed
+                if regions.is_empty() {
+                    return Err(Error::IOError {
+                        context: format!("failed to query the memory of process {}", self.pid),
+                        io_error: IoError::last_os_error(),
+                    });
+                }
+                break;
+            }
+
+            if info.State == MEM_COMMIT {
+                regions.push(MemoryRegion {
+                    base: info.BaseAddress as usize,
+                    size: info.RegionSize,
+                    protection: page_protection(info.Protect),
+                    kind: match info.Type {
+                        MEM_IMAGE => RegionKind::Image,
+                        MEM_MAPPED => RegionKind::Mapped,
+                        _ => RegionKind::Private,
+                    },
+                });
+            }
+
+            match (info.BaseAddress as usize).checked_add(info.RegionSize) {
+                Some(next) if next > address => address = next,
+                _ => break,
+            }
+        }
+
+        Ok(regions)
+    }
+
+    fn read_bytes(&self, address: usize, buffer: &mut [u8]) -> Result<(), Error> {
+        let mut read: usize = 0;
+        let result = unsafe {
+            ReadProcessMemory(
+                self.handle,
+                address as *const _,
+                buffer.as_mut_ptr() as *mut _,
+                buffer.len(),
+                &mut read,
+            )
+        };
+
+        if result != TRUE && read == 0 {
+            if !self.is_running() {
+                return Err(Error::ProcessExited { pid: self.pid });
+            }
+            return Err(Error::UnreadableAddress { address });
+        }
+
+        if read != buffer.len() {
+            return Err(Error::PartialRead {
+                address,
+                expected: buffer.len(),
+                actual: read,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Converts the `PAGE_*` constants of a region, guard pages can't be read.
+fn page_protection(protect: u32) -> Protection {
+    let access = protect & 0xFF;
+    let guarded = protect & PAGE_GUARD != 0 || access == PAGE_NOACCESS;
+    Protection {
+        read: !guarded
+            && matches!(
+                access,
+                PAGE_READONLY
+                    | PAGE_READWRITE
+                    | PAGE_WRITECOPY
+                    | PAGE_EXECUTE_READ
+                    | PAGE_EXECUTE_READWRITE
+                    | PAGE_EXECUTE_WRITECOPY
+            ),
+        write: matches!(
+            access,
+            PAGE_READWRITE | PAGE_WRITECOPY | PAGE_EXECUTE_READWRITE | PAGE_EXECUTE_WRITECOPY
+        ),
+        execute: matches!(
+            access,
+            PAGE_EXECUTE | PAGE_EXECUTE_READ | PAGE_EXECUTE_READWRITE | PAGE_EXECUTE_WRITECOPY
+        ),
+    }
+}
+
+/// Converts a null terminated UTF-16 buffer.
+fn wide_to_string(wide: &[u16]) -> String {
+    let length = wide.iter().take_while(|&&c| c != 0).count();
+    OsString::from_wide(&wide[..length])
+        .to_string_lossy()
+        .into_owned()
+}
+
+impl Drop for WindowsProcess {
+    fn drop(&mut self) {
+        unsafe { CloseHandle(self.handle) };
+    }
+}
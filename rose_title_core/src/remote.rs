@@ -0,0 +1,249 @@
+//! Structs of the game read with a few bulk reads, the layout given by
+//! `#[derive(RemoteStruct)]`:
+//!
+//! ```
+//! use rose_title_core::process_memory::FakeProcess;
+//! use rose_title_core::remote::RemoteStruct;
+//!
+//! #[derive(RemoteStruct)]
+//! struct Player {
+//!     #[offset(0x10)]
+//!     #[string(16)]
+//!     name: String,
+//!     #[offset(0x30)]
+//!     level: u16,
+//! }
+//!
+//! let mut memory = vec![0; 0x40];
+//! memory[0x10..0x16].copy_from_slice(b"Xikeon");
+//! memory[0x30] = 90;
+//! let process = FakeProcess::new(1).with_region(0x1000, memory);
+//!
+//! let player = Player::read(&process, 0x1000).unwrap();
+//! assert_eq!((player.name.as_str(), player.level), ("Xikeon", 90));
+//! ```
+//!
+//! Offsets can be changed at runtime through a `Layout`, e.g. for offsets
+//! profiles of other client versions. `Option` fields are optional: they are only
+//! read when they have an offset, and are `None` when that memory can't be read
+//! instead of failing the whole struct.
+
+pub use rose_title_derive::RemoteStruct;
+
+use crate::encoding::TextEncoding;
+use crate::error::Error;
+use crate::process_memory::{decode_string, MemoryReader};
+
+/// Fields further apart than this are read separately instead of with the bytes
+/// between them.
+const MAX_READ_GAP: usize = 256;
+
+/// Where a field is in the struct and how many bytes it takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldLayout {
+    pub name: &'static str,
+    /// `None` for optional fields that are only read when a `Layout` gives an offset.
+    pub offset: Option<usize>,
+    pub size: usize,
+    /// Whether the field is an `Option` that is read on its own, so it can't fail
+    /// the read of the struct.
+    pub optional: bool,
+}
+
+/// Field offsets and the string encoding to read a struct with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    fields: &'static [FieldLayout],
+    offsets: Vec<Option<usize>>,
+    encoding: TextEncoding,
+}
+
+impl Layout {
+    /// The offsets given in the `#[offset(..)]` attributes.
+    pub fn of<T: RemoteStruct>() -> Self {
+        Layout {
+            fields: T::FIELDS,
+            offsets: T::FIELDS.iter().map(|field| field.offset).collect(),
+            encoding: TextEncoding::default(),
+        }
+    }
+
+    pub fn with_encoding(mut self, encoding: TextEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Moves the field named `name`, `false` if there is no such field.
+    pub fn set_offset(&mut self, name: &str, offset: usize) -> bool {
+        match self.fields.iter().position(|field| field.name == name) {
+            Some(index) => {
+                self.offsets[index] = Some(offset);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Offset of the field named `name`, `None` for unknown fields and optional
+    /// fields without an offset.
+    pub fn offset(&self, name: &str) -> Option<usize> {
+        let index = self.fields.iter().position(|field| field.name == name)?;
+        self.offsets[index]
+    }
+
+    pub fn has_field(&self, name: &str) -> bool {
+        self.fields.iter().any(|field| field.name == name)
+    }
+
+    /// The required fields grouped into runs that are read together, as the start
+    /// and end relative to the struct and the indices of the fields.
+    fn runs(&self) -> Vec<(usize, usize, Vec<usize>)> {
+        let mut fields: Vec<(usize, usize)> = self
+            .fields
+            .iter()
+            .zip(&self.offsets)
+            .enumerate()
+            .filter(|(_, (field, _))| !field.optional)
+            .filter_map(|(index, (_, offset))| Some((index, (*offset)?)))
+            .collect();
+        fields.sort_by_key(|&(_, offset)| offset);
+
+        let mut runs: Vec<(usize, usize, Vec<usize>)> = vec![];
+        for (index, offset) in fields {
+            let end = offset + self.fields[index].size;
+            match runs.last_mut() {
+                Some(run) if offset <= run.1 + MAX_READ_GAP => {
+                    run.1 = run.1.max(end);
+                    run.2.push(index);
+                }
+                _ => runs.push((offset, end, vec![index])),
+            }
+        }
+        runs
+    }
+}
+
+/// A struct in the game's memory, implemented by `#[derive(RemoteStruct)]`.
+pub trait RemoteStruct: Sized {
+    const FIELDS: &'static [FieldLayout];
+
+    /// Builds the struct from the bytes of its fields, following pointers through `process`.
+    fn decode(process: &impl MemoryReader, fields: &FieldBytes) -> Result<Self, Error>;
+
+    /// Reads the struct at `address` with the offsets of its attributes.
+    fn read(process: &impl MemoryReader, address: usize) -> Result<Self, Error> {
+        Self::read_layout(process, address, &Layout::of::<Self>())
+    }
+
+    /// Reads the struct at `address`, nearby fields with a single read and the
+    /// optional fields on their own.
+    fn read_layout(
+        process: &impl MemoryReader,
+        address: usize,
+        layout: &Layout,
+    ) -> Result<Self, Error> {
+        let mut values = vec![None; layout.fields.len()];
+        for (begin, end, indices) in layout.runs() {
+            let mut bytes = vec![0; end - begin];
+            process.read_bytes(address + begin, &mut bytes)?;
+            for index in indices {
+                let start = layout.offsets[index].expect("required field offset") - begin;
+                values[index] = Some(bytes[start..start + layout.fields[index].size].to_vec());
+            }
+        }
+        for (index, field) in layout.fields.iter().enumerate() {
+            if let (true, Some(offset)) = (field.optional, layout.offsets[index]) {
+                let mut bytes = vec![0; field.size];
+                // an unreadable optional field is just missing
+                values[index] = process
+                    .read_bytes(address + offset, &mut bytes)
+                    .ok()
+                    .map(|_| bytes);
+            }
+        }
+
+        Self::decode(
+            process,
+            &FieldBytes {
+                address,
+                values,
+                layout,
+            },
+        )
+    }
+}
+
+/// The bytes of a struct read by `RemoteStruct::read_layout`.
+pub struct FieldBytes<'a> {
+    /// Address of the struct.
+    address: usize,
+    /// The bytes of every field, `None` for optional fields that weren't read.
+    values: Vec<Option<Vec<u8>>>,
+    layout: &'a Layout,
+}
+
+impl FieldBytes<'_> {
+    fn field(&self, index: usize) -> &[u8] {
+        self.values[index].as_deref().expect("required field")
+    }
+
+    pub fn value<T: RemoteValue>(&self, index: usize) -> T {
+        T::from_le_bytes(self.field(index))
+    }
+
+    /// An optional field, `None` when it has no offset or couldn't be read.
+    pub fn optional<T: RemoteValue>(&self, index: usize) -> Option<T> {
+        self.values[index].as_deref().map(T::from_le_bytes)
+    }
+
+    /// A 64-bit pointer.
+    pub fn pointer(&self, index: usize) -> usize {
+        self.value::<u64>(index) as usize
+    }
+
+    /// A string stored inline, ending at the first null byte of its buffer. A string
+    /// without its terminator isn't cut off.
+    pub fn string(&self, index: usize) -> Result<String, Error> {
+        let bytes = self.field(index);
+        let address = self.address + self.layout.offsets[index].expect("string offset");
+        decode_string(bytes, address, self.layout.encoding).unwrap_or(Err(Error::StringTooLong {
+            address,
+            max_length: bytes.len() - 1,
+        }))
+    }
+
+    pub fn encoding(&self) -> TextEncoding {
+        self.layout.encoding
+    }
+}
+
+/// A plain value stored little endian.
+pub trait RemoteValue {
+    const SIZE: usize;
+
+    fn from_le_bytes(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_remote_value {
+    ($($type: ident),*) => {
+        $(
+            impl RemoteValue for $type {
+                const SIZE: usize = std::mem::size_of::<$type>();
+
+                fn from_le_bytes(bytes: &[u8]) -> Self {
+                    $type::from_le_bytes(bytes.try_into().expect("field size"))
+                }
+            }
+        )*
+    };
+}
+
+impl_remote_value!(u8, i8, u16, i16, u32, i32, u64, i64, f32, f64);
+
+impl RemoteValue for bool {
+    const SIZE: usize = 1;
+
+    fn from_le_bytes(bytes: &[u8]) -> Self {
+        bytes[0] != 0
+    }
+}
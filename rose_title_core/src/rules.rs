@@ -0,0 +1,438 @@
+//! Per-character title rules, e.g. to show the role of a character instead of
+//! its name or to leave the window of a shop character alone.
+
+use std::fmt;
+use std::fs;
+use std::io::{Error as IoError, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::character::Character;
+use crate::helpers::job_id_by_name;
+use crate::jobs::JobNames;
+use crate::template::Template;
+use crate::title::{parse_title_template, title_variable};
+
+pub const RULES_FILE_NAME: &str = "rules.toml";
+
+/// What a rule compares with its pattern.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchKind {
+    /// The exact character name.
+    #[default]
+    Name,
+    /// A regular expression the character name has to match.
+    Regex,
+    /// The job id or job name.
+    Job,
+}
+
+impl MatchKind {
+    pub const ALL: [MatchKind; 3] = [MatchKind::Name, MatchKind::Regex, MatchKind::Job];
+}
+
+impl fmt::Display for MatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchKind::Name => write!(f, "name"),
+            MatchKind::Regex => write!(f, "regex"),
+            MatchKind::Job => write!(f, "job"),
+        }
+    }
+}
+
+/// A rule as written in `rules.toml`, empty strings are settings that aren't used.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TitleRule {
+    #[serde(rename = "match")]
+    pub kind: MatchKind,
+    pub pattern: String,
+    /// Replaces the title template for the character.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub template: String,
+    /// Put in front of the title.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub prefix: String,
+    /// The `{alias}` of the character, e.g. its role.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub alias: String,
+    /// Leave the window title alone.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub exclude: bool,
+}
+
+#[derive(Debug)]
+pub enum RulesError {
+    IOError {
+        path: PathBuf,
+        io_error: IoError,
+    },
+    ParseError {
+        path: PathBuf,
+        message: String,
+    },
+    /// The rules couldn't be turned into TOML to save them.
+    SerializeError {
+        path: PathBuf,
+        message: String,
+    },
+    /// `index` counts from 1 like the rules in the file.
+    InvalidRule {
+        index: usize,
+        reason: String,
+    },
+}
+
+impl fmt::Display for RulesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RulesError::IOError { path, io_error } => {
+                write!(f, "failed to access {}: {}", path.display(), io_error)
+            }
+            RulesError::ParseError { path, message } => {
+                write!(f, "failed to parse {}: {}", path.display(), message)
+            }
+            RulesError::SerializeError { path, message } => {
+                write!(f, "failed to write {}: {}", path.display(), message)
+            }
+            RulesError::InvalidRule { index, reason } => {
+                write!(f, "invalid title rule {}: {}", index, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RulesError {}
+
+/// The rules in the order they are tried, the first matching rule applies.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TitleRules {
+    #[serde(rename = "rule", default)]
+    pub rules: Vec<TitleRule>,
+}
+
+impl TitleRules {
+    pub fn load(path: &Path) -> Result<Self, RulesError> {
+        let text = fs::read_to_string(path).map_err(|io_error| RulesError::IOError {
+            path: path.into(),
+            io_error,
+        })?;
+        Self::parse(&text, path)
+    }
+
+    /// Loads `rules.toml` next to the executable, no rules when there is none.
+    pub fn load_default() -> Result<Self, RulesError> {
+        let Some(path) = default_path() else {
+            return Ok(Self::default());
+        };
+        match Self::load(&path) {
+            Err(RulesError::IOError { io_error, .. }) if io_error.kind() == ErrorKind::NotFound => {
+                Ok(Self::default())
+            }
+            result => result,
+        }
+    }
+
+    pub fn parse(text: &str, path: &Path) -> Result<Self, RulesError> {
+        toml::from_str(text).map_err(|error| RulesError::ParseError {
+            path: path.into(),
+            message: error.to_string(),
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), RulesError> {
+        let text = toml::to_string(self).map_err(|error| RulesError::SerializeError {
+            path: path.into(),
+            message: error.to_string(),
+        })?;
+        fs::write(path, text).map_err(|io_error| RulesError::IOError {
+            path: path.into(),
+            io_error,
+        })
+    }
+
+    /// Checks the patterns and templates of the rules, job names are looked up in
+    /// `job_names` and the built-in names.
+    pub fn compile(&self, job_names: &JobNames) -> Result<RuleSet, RulesError> {
+        let rules = self
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| {
+                CompiledRule::new(rule, job_names).map_err(|reason| RulesError::InvalidRule {
+                    index: index + 1,
+                    reason,
+                })
+            })
+            .collect::<Result<Vec<CompiledRule>, RulesError>>()?;
+        Ok(RuleSet { rules })
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Name(String),
+    Regex(Regex),
+    Job(u32),
+}
+
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    matcher: Matcher,
+    template: Option<Template>,
+    prefix: String,
+    alias: String,
+    exclude: bool,
+}
+
+impl CompiledRule {
+    fn new(rule: &TitleRule, job_names: &JobNames) -> Result<CompiledRule, String> {
+        if rule.pattern.is_empty() {
+            return Err(format!("the {} to match is empty", rule.kind));
+        }
+        let matcher = match rule.kind {
+            MatchKind::Name => Matcher::Name(rule.pattern.clone()),
+            MatchKind::Regex => {
+                Matcher::Regex(Regex::new(&rule.pattern).map_err(|error| error.to_string())?)
+            }
+            MatchKind::Job => Matcher::Job(
+                rule.pattern
+                    .parse()
+                    .ok()
+                    .or_else(|| job_id_by_name(job_names, &rule.pattern))
+                    .ok_or_else(|| format!("unknown job {}", rule.pattern))?,
+            ),
+        };
+        let template = match rule.template.as_str() {
+            "" => None,
+            template => Some(parse_title_template(template).map_err(|error| error.to_string())?),
+        };
+
+        Ok(CompiledRule {
+            matcher,
+            template,
+            prefix: rule.prefix.clone(),
+            alias: rule.alias.clone(),
+            exclude: rule.exclude,
+        })
+    }
+
+    fn matches(&self, character: &Character) -> bool {
+        match &self.matcher {
+            Matcher::Name(name) => character.name == *name,
+            Matcher::Regex(regex) => regex.is_match(&character.name),
+            Matcher::Job(job_id) => character.job_id == *job_id,
+        }
+    }
+}
+
+/// Compiled `TitleRules`, ready to be matched against characters.
+#[derive(Debug, Clone, Default)]
+pub struct RuleSet {
+    rules: Vec<CompiledRule>,
+}
+
+impl RuleSet {
+    /// Title of the character in `slot` with the first matching rule applied,
+    /// `None` when the rule excludes its window.
+    pub fn format_title(
+        &self,
+        character: &Character,
+        job_names: &JobNames,
+        slot: Option<u32>,
+        template: &Template,
+    ) -> Option<String> {
+        let rule = self.rules.iter().find(|rule| rule.matches(character));
+        if rule.is_some_and(|rule| rule.exclude) {
+            return None;
+        }
+
+        let template = rule
+            .and_then(|rule| rule.template.as_ref())
+            .unwrap_or(template);
+        let title = template.render(|name| match name {
+            "alias" => rule.map(|rule| rule.alias.clone()),
+            "slot" => slot.map(|slot| slot.to_string()),
+            name => title_variable(character, job_names, name),
+        });
+        let prefix = rule.map_or("", |rule| rule.prefix.as_str());
+        Some(format!("{}{}", prefix, title))
+    }
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    Some(
+        std::env::current_exe()
+            .ok()?
+            .with_file_name(RULES_FILE_NAME),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::title::TitleOptions;
+
+    const RULES: &str = r#"
+[[rule]]
+match = "name"
+pattern = "Xikeon"
+alias = "Tank"
+
+[[rule]]
+match = "regex"
+pattern = "^Buff"
+prefix = "[B] "
+template = "{name} Lv{level}"
+
+[[rule]]
+match = "job"
+pattern = "dealer"
+exclude = true
+"#;
+
+    fn rules() -> RuleSet {
+        TitleRules::parse(RULES, Path::new("rules.toml"))
+            .unwrap()
+            .compile(&JobNames::default())
+            .unwrap()
+    }
+
+    fn character(name: &str, job_id: u32) -> Character {
+        Character {
+            name: name.into(),
+            job_id,
+            level: Some(90),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn applies_the_first_matching_rule() {
+        let rules = rules();
+        let template = TitleOptions::default().template();
+
+        assert_eq!(
+            rules.format_title(
+                &character("Xikeon", 411),
+                &JobNames::default(),
+                None,
+                &template
+            ),
+            Some("Xikeon (Tank) - Dealer".into())
+        );
+        assert_eq!(
+            rules.format_title(
+                &character("BuffBot", 222),
+                &JobNames::default(),
+                None,
+                &template
+            ),
+            Some("[B] BuffBot Lv90".into())
+        );
+        assert_eq!(
+            rules.format_title(
+                &character("Shopkeeper", 411),
+                &JobNames::default(),
+                None,
+                &template
+            ),
+            None
+        );
+        assert_eq!(
+            rules.format_title(
+                &character("Other", 221),
+                &JobNames::default(),
+                None,
+                &template
+            ),
+            Some("Other - Mage".into())
+        );
+
+        let slot_template = parse_title_template("{?slot}#{slot} {/}{name}").unwrap();
+        assert_eq!(
+            rules.format_title(
+                &character("Other", 221),
+                &JobNames::default(),
+                Some(2),
+                &slot_template
+            ),
+            Some("#2 Other".into())
+        );
+        assert_eq!(
+            rules.format_title(
+                &character("Other", 221),
+                &JobNames::default(),
+                None,
+                &slot_template
+            ),
+            Some("Other".into())
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        let compile = |text: &str| {
+            TitleRules::parse(text, Path::new("rules.toml"))
+                .unwrap()
+                .compile(&JobNames::default())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            compile(&RULES.replace("dealer", "banker")),
+            "invalid title rule 3: unknown job banker"
+        );
+        assert!(compile(&RULES.replace("^Buff", "(Buff")).starts_with("invalid title rule 2: "));
+        assert!(compile(&RULES.replace("{level}", "{lvl}"))
+            .starts_with("invalid title rule 2: unknown variable `lvl`"));
+        assert_eq!(
+            compile(&RULES.replace("\"Xikeon\"", "\"\"")),
+            "invalid title rule 1: the name to match is empty"
+        );
+    }
+
+    #[test]
+    fn matches_jobs_by_the_client_names() {
+        let data_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+        let job_names = JobNames::load(&data_dir, 1, crate::encoding::TextEncoding::Utf8).unwrap();
+        let text = RULES.replace("dealer", "berserker");
+        let rules = TitleRules::parse(&text, Path::new("rules.toml")).unwrap();
+
+        assert!(rules.compile(&JobNames::default()).is_err());
+        let template = TitleOptions::default().template();
+        assert_eq!(
+            rules.compile(&job_names).unwrap().format_title(
+                &character("Other", 131),
+                &job_names,
+                None,
+                &template
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn saves_only_the_settings_in_use() {
+        let rules = TitleRules {
+            rules: vec![TitleRule {
+                kind: MatchKind::Job,
+                pattern: "411".into(),
+                exclude: true,
+                ..Default::default()
+            }],
+        };
+        let text = toml::to_string(&rules).unwrap();
+        assert_eq!(
+            text,
+            "[[rule]]\nmatch = \"job\"\npattern = \"411\"\nexclude = true\n"
+        );
+        assert_eq!(
+            TitleRules::parse(&text, Path::new("rules.toml")).unwrap(),
+            rules
+        );
+    }
+}
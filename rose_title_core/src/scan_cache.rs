@@ -0,0 +1,326 @@
+use std::fmt;
+use std::fs;
+use std::io::Error as IoError;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::helpers::APP_DIR_NAME;
+use crate::process_memory::MemoryReader;
+use crate::signature::Signature;
+use crate::signatures::{SignatureDatabase, SignatureMatch};
+
+pub const SCAN_CACHE_FILE_NAME: &str = "scan_cache.toml";
+
+/// Where a signature was found in one build of the client, relative to the module base.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanCacheEntry {
+    pub build: String,
+    pub signature: String,
+    pub rva: usize,
+}
+
+/// Signature scan results of earlier runs, so restarting doesn't scan the module again.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanCache {
+    #[serde(rename = "entry", default)]
+    pub entries: Vec<ScanCacheEntry>,
+    /// Whether there are entries that haven't been saved yet.
+    #[serde(skip)]
+    changed: bool,
+}
+
+#[derive(Debug)]
+pub enum ScanCacheError {
+    IOError {
+        path: PathBuf,
+        io_error: IoError,
+    },
+    ParseError {
+        path: PathBuf,
+        message: String,
+    },
+    /// The file contents couldn't be turned into TOML to save them.
+    SerializeError {
+        path: PathBuf,
+        message: String,
+    },
+}
+
+impl fmt::Display for ScanCacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanCacheError::IOError { path, io_error } => {
+                write!(f, "failed to access {}: {}", path.display(), io_error)
+            }
+            ScanCacheError::ParseError { path, message } => {
+                write!(f, "failed to parse {}: {}", path.display(), message)
+            }
+            ScanCacheError::SerializeError { path, message } => {
+                write!(f, "failed to write {}: {}", path.display(), message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScanCacheError {}
+
+impl ScanCache {
+    pub fn load(path: &Path) -> Result<Self, ScanCacheError> {
+        let text = fs::read_to_string(path).map_err(|io_error| ScanCacheError::IOError {
+            path: path.into(),
+            io_error,
+        })?;
+        toml::from_str(&text).map_err(|error| ScanCacheError::ParseError {
+            path: path.into(),
+            message: error.to_string(),
+        })
+    }
+
+    /// Loads the cache, starting over with an empty one when it's missing or broken.
+    pub fn load_or_default(path: &Path) -> Self {
+        Self::load(path).unwrap_or_default()
+    }
+
+    /// Saves the cache if entries were added since it was loaded.
+    pub fn save_if_changed(&mut self, path: &Path) -> Result<(), ScanCacheError> {
+        if !self.changed {
+            return Ok(());
+        }
+        self.save(path)?;
+        self.changed = false;
+        Ok(())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ScanCacheError> {
+        let text = toml::to_string(self).map_err(|error| ScanCacheError::SerializeError {
+            path: path.into(),
+            message: error.to_string(),
+        })?;
+        let io_error = |io_error| ScanCacheError::IOError {
+            path: path.into(),
+            io_error,
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(io_error)?;
+        }
+        fs::write(path, text).map_err(io_error)
+    }
+
+    pub fn get(&self, build: &str, signature: &str) -> Option<usize> {
+        self.entries
+            .iter()
+            .find(|entry| entry.build == build && entry.signature == signature)
+            .map(|entry| entry.rva)
+    }
+
+    pub fn insert(&mut self, build: &str, signature: &str, rva: usize) {
+        self.entries
+            .retain(|entry| !(entry.build == build && entry.signature == signature));
+        self.entries.push(ScanCacheEntry {
+            build: build.into(),
+            signature: signature.into(),
+            rva,
+        });
+        self.changed = true;
+    }
+
+    /// Finds the player signature like `SignatureDatabase::find_player`, trying the
+    /// cached addresses of this client build first.
+    ///
+    /// A cached address is only used when the bytes there still match the pattern,
+    /// otherwise the module is scanned and the cache updated.
+    pub fn find_player<'a>(
+        &mut self,
+        signatures: &'a SignatureDatabase,
+        process: &impl MemoryReader,
+        module: &str,
+    ) -> Result<Option<SignatureMatch<'a>>, Error> {
+        let module_begin = process.module(module)?.base;
+        let build = match client_build_key(process, module_begin) {
+            Some(build) => build,
+            // without a key the cache can't tell builds apart
+            None => return signatures.find_player(process, module),
+        };
+
+        for entry in &signatures.player {
+            let address = match self.get(&build, &entry.name) {
+                Some(rva) => module_begin + rva,
+                None => continue,
+            };
+            if verify_signature(process, &entry.pattern, address) {
+                let trace = entry.player_path().trace_after(process, 0, address);
+                return Ok(Some(SignatureMatch { entry, trace }));
+            }
+        }
+
+        let found = signatures.find_player(process, module)?;
+        if let Some(found) = &found {
+            let rva = found.trace.addresses[0] - module_begin;
+            self.insert(&build, &found.entry.name, rva);
+        }
+        Ok(found)
+    }
+}
+
+fn verify_signature(process: &impl MemoryReader, pattern: &str, address: usize) -> bool {
+    let signature: Signature = match pattern.parse() {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+    let mut bytes = vec![0; signature.len()];
+    process.read_bytes(address, &mut bytes).is_ok() && signature.matches(&bytes)
+}
+
+/// Identifies the build of the module at `module_begin` by the link timestamp and
+/// image size in its PE header, the same key symbol servers use.
+pub fn client_build_key(process: &impl MemoryReader, module_begin: usize) -> Option<String> {
+    let mut dos_magic = [0u8; 2];
+    process.read_bytes(module_begin, &mut dos_magic).ok()?;
+    if &dos_magic != b"MZ" {
+        return None;
+    }
+
+    let pe_header = module_begin + process.read_u32(module_begin + 0x3C).ok()? as usize;
+    let mut pe_magic = [0u8; 4];
+    process.read_bytes(pe_header, &mut pe_magic).ok()?;
+    if &pe_magic != b"PE\0\0" {
+        return None;
+    }
+
+    // the optional header follows the 4 byte magic and the 20 byte file header
+    let time_date_stamp = process.read_u32(pe_header + 8).ok()?;
+    let size_of_image = process.read_u32(pe_header + 24 + 56).ok()?;
+    Some(format!("{:08X}{:x}", time_date_stamp, size_of_image))
+}
+
+/// `scan_cache.toml` in the user's cache directory, the directory of the
+/// executable may not be writable.
+pub fn default_path() -> Option<PathBuf> {
+    Some(
+        dirs::cache_dir()?
+            .join(APP_DIR_NAME)
+            .join(SCAN_CACHE_FILE_NAME),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process_memory::FakeProcess;
+
+    const MODULE_BASE: usize = 0x1_4000_0000;
+    const PLAYER_ADDRESS: usize = 0x2_0000_0000;
+    const BUILD: &str = "5F3A1C2Bd2000";
+
+    /// A module with a PE header and the player signature at 0x400 and 0x800,
+    /// both pointing at the player pointer at 0x1000.
+    fn process() -> FakeProcess {
+        let mut module = vec![0u8; 0x2000];
+        module[..2].copy_from_slice(b"MZ");
+        module[0x3C..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        module[0x80..0x84].copy_from_slice(b"PE\0\0");
+        module[0x88..0x8C].copy_from_slice(&0x5F3A_1C2Bu32.to_le_bytes());
+        module[0xD0..0xD4].copy_from_slice(&0xD2000u32.to_le_bytes());
+
+        let pattern = &SignatureDatabase::builtin().player[0].pattern;
+        for signature_offset in [0x400, 0x800] {
+            for (i, byte) in pattern.split(' ').enumerate() {
+                module[signature_offset + i] = u8::from_str_radix(byte, 16).unwrap_or(0x48);
+            }
+            let displacement = (0x1000 - signature_offset - 11) as u32;
+            module[signature_offset + 7..signature_offset + 11]
+                .copy_from_slice(&displacement.to_le_bytes());
+        }
+        module[0x1000..0x1008].copy_from_slice(&(PLAYER_ADDRESS as u64).to_le_bytes());
+
+        FakeProcess::new(1)
+            .with_module("trose.exe", MODULE_BASE, module)
+            .with_region(PLAYER_ADDRESS, vec![0; 0x10])
+    }
+
+    #[test]
+    fn reads_build_key_from_pe_header() {
+        assert_eq!(
+            client_build_key(&process(), MODULE_BASE).as_deref(),
+            Some(BUILD)
+        );
+        let not_pe = FakeProcess::new(1).with_region(MODULE_BASE, vec![0; 0x100]);
+        assert_eq!(client_build_key(&not_pe, MODULE_BASE), None);
+    }
+
+    #[test]
+    fn caches_scan_results() {
+        let signatures = SignatureDatabase::builtin();
+        let process = process();
+        let mut cache = ScanCache::default();
+
+        let found = cache
+            .find_player(&signatures, &process, "trose.exe")
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.trace.addresses[0], MODULE_BASE + 0x400);
+        assert_eq!(cache.get(BUILD, &signatures.player[0].name), Some(0x400));
+        assert!(cache.changed);
+    }
+
+    #[test]
+    fn uses_verified_cached_address() {
+        let signatures = SignatureDatabase::builtin();
+        let process = process();
+        let mut cache = ScanCache::default();
+        // a scan would find the copy at 0x400 first
+        cache.insert(BUILD, &signatures.player[0].name, 0x800);
+        cache.changed = false;
+
+        let found = cache
+            .find_player(&signatures, &process, "trose.exe")
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.trace.addresses[0], MODULE_BASE + 0x800);
+        assert_eq!(found.trace.into_result().unwrap(), PLAYER_ADDRESS);
+        assert!(!cache.changed);
+    }
+
+    #[test]
+    fn rescans_when_cached_bytes_differ() {
+        let signatures = SignatureDatabase::builtin();
+        let process = process();
+        let mut cache = ScanCache::default();
+        cache.insert(BUILD, &signatures.player[0].name, 0x600);
+
+        let found = cache
+            .find_player(&signatures, &process, "trose.exe")
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.trace.addresses[0], MODULE_BASE + 0x400);
+        assert_eq!(cache.get(BUILD, &signatures.player[0].name), Some(0x400));
+        assert_eq!(cache.entries.len(), 1);
+    }
+
+    #[test]
+    fn saves_and_loads_entries() {
+        // the directory is created on the first save
+        let dir = std::env::temp_dir().join(format!("scan_cache_{}", std::process::id()));
+        let path = dir.join(SCAN_CACHE_FILE_NAME);
+        let mut cache = ScanCache::default();
+        cache.insert(BUILD, "player-2022", 0x1234);
+        cache.save_if_changed(&path).unwrap();
+        assert!(!cache.changed);
+
+        let loaded = ScanCache::load(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(loaded.entries, cache.entries);
+        assert!(ScanCache::load_or_default(&path).entries.is_empty());
+
+        // a failed save is reported and tried again
+        cache.insert(BUILD, "player-2023", 0x5678);
+        let file = std::env::temp_dir().join(format!("scan_cache_file_{}", std::process::id()));
+        fs::write(&file, "").unwrap();
+        let result = cache.save_if_changed(&file.join(SCAN_CACHE_FILE_NAME));
+        fs::remove_file(&file).unwrap();
+        assert!(matches!(result, Err(ScanCacheError::IOError { .. })));
+        assert!(cache.changed);
+    }
+}
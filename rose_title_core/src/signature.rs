@@ -0,0 +1,472 @@
+use std::fmt;
+use std::str::FromStr;
+
+use memchr::memchr_iter;
+
+use crate::error::Error;
+use crate::process_memory::{MemoryReader, MemoryRegion, RegionKind};
+
+/// Unreadable memory is skipped with page granularity.
+const PAGE_SIZE: usize = 4096;
+/// Amount of memory fetched per read, so a module takes a handful of reads.
+const BLOCK_SIZE: usize = 1 << 20;
+
+/// Bytes that show up everywhere in x64 code and padding, bad candidates for
+/// the byte `memchr` looks for.
+const COMMON_BYTES: [u8; 6] = [0x00, 0xFF, 0xCC, 0x48, 0x89, 0x8B];
+
+/// A byte pattern like `"48 8B 05 ? ? ? ?"`, where `?` matches any byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    /// Expected bytes, 0 for wildcards.
+    pattern: Vec<u8>,
+    /// 0xFF for fixed bytes, 0 for wildcards.
+    mask: Vec<u8>,
+    /// Index of the fixed byte candidates are searched by.
+    anchor: Option<usize>,
+}
+
+/// The memory a signature scan covers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanTarget {
+    Range {
+        begin: usize,
+        end: usize,
+    },
+    /// The image of the module with this name.
+    Module(String),
+    /// Code of every module plus memory made executable at runtime.
+    ExecutableRegions,
+    /// Heap and other readable memory that isn't mapped from a file.
+    ReadablePrivateRegions,
+}
+
+impl ScanTarget {
+    /// Address ranges covered by the target, adjacent regions joined into one
+    /// range so matches can span across them.
+    ///
+    /// Fails when the module isn't loaded or the regions can't be listed, rather
+    /// than scanning nothing.
+    pub fn ranges(&self, process: &impl MemoryReader) -> Result<Vec<(usize, usize)>, Error> {
+        let filter: fn(&MemoryRegion) -> bool = match self {
+            ScanTarget::Range { begin, end } => return Ok(vec![(*begin, *end)]),
+            ScanTarget::Module(name) => {
+                let module = process.module(name)?;
+                return Ok(vec![(module.base, module.end())]);
+            }
+            ScanTarget::ExecutableRegions => {
+                |region| region.protection.read && region.protection.execute
+            }
+            ScanTarget::ReadablePrivateRegions => {
+                |region| region.protection.read && region.kind == RegionKind::Private
+            }
+        };
+
+        let mut ranges: Vec<(usize, usize)> = vec![];
+        for region in process.regions()? {
+            if !filter(&region) {
+                continue;
+            }
+            match ranges.last_mut() {
+                Some((_, end)) if *end == region.base => *end = region.end(),
+                _ => ranges.push((region.base, region.end())),
+            }
+        }
+        Ok(ranges)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SignatureParseError {
+    Empty,
+    InvalidByte { index: usize, token: String },
+}
+
+impl fmt::Display for SignatureParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureParseError::Empty => write!(f, "signature is empty"),
+            SignatureParseError::InvalidByte { index, token } => {
+                write!(f, "invalid byte \"{}\" at index {}", token, index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SignatureParseError {}
+
+impl FromStr for Signature {
+    type Err = SignatureParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let bytes = text
+            .split_whitespace()
+            .enumerate()
+            .map(|(index, token)| match token {
+                "?" | "??" => Ok(None),
+                _ if token.len() == 2 => u8::from_str_radix(token, 16).map(Some).map_err(|_| {
+                    SignatureParseError::InvalidByte {
+                        index,
+                        token: token.into(),
+                    }
+                }),
+                _ => Err(SignatureParseError::InvalidByte {
+                    index,
+                    token: token.into(),
+                }),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if bytes.is_empty() {
+            return Err(SignatureParseError::Empty);
+        }
+
+        let anchor = bytes
+            .iter()
+            .position(|byte| byte.is_some_and(|byte| !COMMON_BYTES.contains(&byte)))
+            .or_else(|| bytes.iter().position(|byte| byte.is_some()));
+        Ok(Signature {
+            pattern: bytes.iter().map(|byte| byte.unwrap_or(0)).collect(),
+            mask: bytes
+                .iter()
+                .map(|byte| if byte.is_some() { 0xFF } else { 0 })
+                .collect(),
+            anchor,
+        })
+    }
+}
+
+impl Signature {
+    pub fn len(&self) -> usize {
+        self.pattern.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pattern.is_empty()
+    }
+
+    /// Compares a window of exactly `len` bytes, written so it vectorizes.
+    fn matches_at(&self, window: &[u8]) -> bool {
+        window
+            .iter()
+            .zip(&self.mask)
+            .zip(&self.pattern)
+            .fold(0, |diff, ((byte, mask), expected)| {
+                diff | ((byte & mask) ^ expected)
+            })
+            == 0
+    }
+
+    /// Whether `bytes` starts with a match.
+    pub fn matches(&self, bytes: &[u8]) -> bool {
+        bytes.len() >= self.len() && self.matches_at(&bytes[..self.len()])
+    }
+
+    /// Offset of the first match in `haystack`.
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        let last_start = haystack.len().checked_sub(self.len())?;
+        let anchor = match self.anchor {
+            Some(anchor) => anchor,
+            None => return Some(0),
+        };
+
+        memchr_iter(
+            self.pattern[anchor],
+            &haystack[anchor..=last_start + anchor],
+        )
+        .find(|&start| self.matches_at(&haystack[start..start + self.len()]))
+    }
+
+    /// Address of the first match between `begin` and `end`.
+    ///
+    /// Memory is read in large blocks. If a block can't be read as a whole it is
+    /// read again page by page, unreadable pages are skipped and no match spans
+    /// across them.
+    pub fn scan(&self, process: &impl MemoryReader, begin: usize, end: usize) -> Option<usize> {
+        self.scan_blocks(process, begin, end, BLOCK_SIZE)
+    }
+
+    /// Address of the first match in the memory covered by `target`.
+    pub fn scan_target(
+        &self,
+        process: &impl MemoryReader,
+        target: &ScanTarget,
+    ) -> Result<Option<usize>, Error> {
+        Ok(target
+            .ranges(process)?
+            .into_iter()
+            .find_map(|(begin, end)| self.scan(process, begin, end)))
+    }
+
+    fn scan_blocks(
+        &self,
+        process: &impl MemoryReader,
+        begin: usize,
+        end: usize,
+        block_size: usize,
+    ) -> Option<usize> {
+        let mut seam = Seam::default();
+        let mut block = vec![0; block_size.min(end.saturating_sub(begin))];
+
+        let mut block_start = begin;
+        while block_start < end {
+            let block_end = (block_start - block_start % PAGE_SIZE + block_size).min(end);
+            let block = &mut block[..block_end - block_start];
+
+            if process.read_bytes(block_start, block).is_ok() {
+                if let Some(address) = seam.search(self, block_start, block) {
+                    return Some(address);
+                }
+            } else {
+                let mut page_start = block_start;
+                while page_start < block_end {
+                    let page_end = (page_start - page_start % PAGE_SIZE + PAGE_SIZE).min(block_end);
+                    let page = &mut block[page_start - block_start..page_end - block_start];
+                    if process.read_bytes(page_start, page).is_ok() {
+                        if let Some(address) = seam.search(self, page_start, page) {
+                            return Some(address);
+                        }
+                    }
+                    page_start = page_end;
+                }
+            }
+
+            block_start = block_end;
+        }
+
+        None
+    }
+}
+
+/// The last `len - 1` bytes of the previous read, used to find matches that
+/// start in one read and end in the next.
+#[derive(Default)]
+struct Seam {
+    bytes: Vec<u8>,
+    end: usize,
+}
+
+impl Seam {
+    fn search(&mut self, signature: &Signature, address: usize, bytes: &[u8]) -> Option<usize> {
+        let overlap = signature.len() - 1;
+        if self.end != address {
+            // the memory in between wasn't readable
+            self.bytes.clear();
+        }
+
+        if !self.bytes.is_empty() {
+            let seam_start = address - self.bytes.len();
+            self.bytes
+                .extend_from_slice(&bytes[..bytes.len().min(overlap)]);
+            if let Some(offset) = signature.find(&self.bytes) {
+                return Some(seam_start + offset);
+            }
+            self.bytes.truncate(address - seam_start);
+        }
+        if let Some(offset) = signature.find(bytes) {
+            return Some(address + offset);
+        }
+
+        if bytes.len() >= overlap {
+            self.bytes.clear();
+            self.bytes
+                .extend_from_slice(&bytes[bytes.len() - overlap..]);
+        } else {
+            self.bytes.extend_from_slice(bytes);
+            let keep = self.bytes.len().min(overlap);
+            self.bytes.drain(..self.bytes.len() - keep);
+        }
+        self.end = address + bytes.len();
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process_memory::{FakeProcess, Protection};
+
+    const BASE: usize = 0x1_4000_0000;
+    const PATTERN: [u8; 7] = [0x48, 0x8B, 0x05, 0x11, 0x22, 0x33, 0x44];
+
+    fn signature() -> Signature {
+        "48 8B 05 ? ? 33 ??".parse().unwrap()
+    }
+
+    #[test]
+    fn parses_signatures() {
+        assert_eq!(signature().len(), 7);
+        assert_eq!("".parse::<Signature>(), Err(SignatureParseError::Empty));
+        assert_eq!(
+            "48 8G".parse::<Signature>(),
+            Err(SignatureParseError::InvalidByte {
+                index: 1,
+                token: "8G".into()
+            })
+        );
+        assert!("488B".parse::<Signature>().is_err());
+    }
+
+    #[test]
+    fn finds_exact_offset_with_wildcards() {
+        let mut haystack = vec![0x48; 32];
+        haystack[9..16].copy_from_slice(&PATTERN);
+        assert_eq!(signature().find(&haystack), Some(9));
+        assert_eq!(
+            "? 8B 05".parse::<Signature>().unwrap().find(&haystack),
+            Some(9)
+        );
+        assert_eq!(signature().find(&haystack[..15]), None);
+    }
+
+    #[test]
+    fn finds_pattern_at_every_boundary_offset() {
+        let signature = signature();
+        for block_size in [PAGE_SIZE, 2 * PAGE_SIZE, BLOCK_SIZE] {
+            for boundary in [PAGE_SIZE, 2 * PAGE_SIZE] {
+                for offset in boundary - PATTERN.len()..=boundary {
+                    let mut memory = vec![0; 3 * PAGE_SIZE];
+                    memory[offset..offset + PATTERN.len()].copy_from_slice(&PATTERN);
+                    let process = FakeProcess::new(1).with_region(BASE, memory);
+
+                    assert_eq!(
+                        signature.scan_blocks(&process, BASE, BASE + 3 * PAGE_SIZE, block_size),
+                        Some(BASE + offset),
+                        "pattern at offset {:#x}, block size {:#x}",
+                        offset,
+                        block_size
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn finds_first_of_several_matches() {
+        let signature = signature();
+        let mut memory = vec![0; 3 * PAGE_SIZE];
+        memory[2 * PAGE_SIZE + 1..2 * PAGE_SIZE + 8].copy_from_slice(&PATTERN);
+        memory[PAGE_SIZE - 2..PAGE_SIZE + 5].copy_from_slice(&PATTERN);
+        let process = FakeProcess::new(1).with_region(BASE, memory);
+
+        for block_size in [PAGE_SIZE, BLOCK_SIZE] {
+            assert_eq!(
+                signature.scan_blocks(&process, BASE, BASE + 3 * PAGE_SIZE, block_size),
+                Some(BASE + PAGE_SIZE - 2)
+            );
+        }
+    }
+
+    #[test]
+    fn matches_only_wildcard_signatures_anywhere() {
+        let signature: Signature = "? ??".parse().unwrap();
+        assert_eq!(signature.find(&[1, 2, 3]), Some(0));
+        assert_eq!(signature.find(&[1]), None);
+    }
+
+    #[test]
+    fn finds_pattern_at_range_edges() {
+        let signature = signature();
+        let mut memory = vec![0; PAGE_SIZE + 100];
+        memory[PAGE_SIZE + 100 - PATTERN.len()..].copy_from_slice(&PATTERN);
+        let process = FakeProcess::new(1).with_region(BASE + 10, memory);
+        let end = BASE + 10 + PAGE_SIZE + 100;
+
+        assert_eq!(
+            signature.scan(&process, BASE + 10, end),
+            Some(end - PATTERN.len())
+        );
+        assert_eq!(signature.scan(&process, BASE + 10, end - 1), None);
+    }
+
+    #[test]
+    fn skips_unreadable_pages() {
+        let signature = signature();
+        let mut first = vec![0; PAGE_SIZE];
+        first[PAGE_SIZE - 3..].copy_from_slice(&PATTERN[..3]);
+        let mut second = vec![0; PAGE_SIZE];
+        second[..4].copy_from_slice(&PATTERN[3..]);
+        second[0x80..0x87].copy_from_slice(&PATTERN);
+
+        // the page between both regions is not mapped
+        let process = FakeProcess::new(1)
+            .with_region(BASE, first)
+            .with_region(BASE + 2 * PAGE_SIZE, second);
+
+        assert_eq!(
+            signature.scan(&process, BASE, BASE + 3 * PAGE_SIZE),
+            Some(BASE + 2 * PAGE_SIZE + 0x80)
+        );
+        assert_eq!(signature.scan(&process, BASE, BASE + 2 * PAGE_SIZE), None);
+    }
+
+    #[test]
+    fn scans_regions_by_protection_and_kind() {
+        let signature = signature();
+        let mut module = vec![0; PAGE_SIZE];
+        module[0x10..0x17].copy_from_slice(&PATTERN);
+        let mut jit = vec![0; PAGE_SIZE];
+        jit[0x20..0x27].copy_from_slice(&PATTERN);
+        let mut heap = vec![0; PAGE_SIZE];
+        heap[0x30..0x37].copy_from_slice(&PATTERN);
+        let executable = Protection {
+            read: true,
+            write: true,
+            execute: true,
+        };
+        let process = FakeProcess::new(1)
+            .with_region(BASE, heap)
+            .with_protected_region(BASE + PAGE_SIZE, jit, executable, RegionKind::Private)
+            .with_module("trose.exe", BASE + 0x10000, module);
+
+        assert_eq!(
+            signature
+                .scan_target(&process, &ScanTarget::ExecutableRegions)
+                .unwrap(),
+            Some(BASE + PAGE_SIZE + 0x20)
+        );
+        assert_eq!(
+            signature
+                .scan_target(&process, &ScanTarget::ReadablePrivateRegions)
+                .unwrap(),
+            Some(BASE + 0x30)
+        );
+        assert_eq!(
+            signature
+                .scan_target(&process, &ScanTarget::Module("trose.exe".into()))
+                .unwrap(),
+            Some(BASE + 0x10010)
+        );
+        // a module that isn't loaded is an error instead of nothing to scan
+        assert!(matches!(
+            signature.scan_target(&process, &ScanTarget::Module("ntdll.dll".into())),
+            Err(Error::ModuleNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn joins_adjacent_regions() {
+        let signature = signature();
+        let mut first = vec![0; PAGE_SIZE];
+        first[PAGE_SIZE - 3..].copy_from_slice(&PATTERN[..3]);
+        let mut second = vec![0; PAGE_SIZE];
+        second[..4].copy_from_slice(&PATTERN[3..]);
+        let process = FakeProcess::new(1)
+            .with_region(BASE + PAGE_SIZE, second)
+            .with_region(BASE, first)
+            .with_region(BASE + 4 * PAGE_SIZE, vec![0; PAGE_SIZE]);
+
+        let target = ScanTarget::ReadablePrivateRegions;
+        assert_eq!(
+            target.ranges(&process).unwrap(),
+            vec![
+                (BASE, BASE + 2 * PAGE_SIZE),
+                (BASE + 4 * PAGE_SIZE, BASE + 5 * PAGE_SIZE)
+            ]
+        );
+        assert_eq!(
+            signature.scan_target(&process, &target).unwrap(),
+            Some(BASE + PAGE_SIZE - 3)
+        );
+    }
+}
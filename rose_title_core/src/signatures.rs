@@ -0,0 +1,371 @@
+use std::fmt;
+use std::fs;
+use std::io::Error as IoError;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::pe::PeFile;
+use crate::process_memory::{
+    MemoryReader, PointerErrorKind, PointerPath, PointerPathError, PointerStep, PointerTrace,
+};
+use crate::signature::Signature;
+
+const BUILTIN_SIGNATURES: &str = include_str!("../signatures.toml");
+pub const SIGNATURES_FILE_NAME: &str = "signatures.toml";
+/// Newest database format this build understands.
+pub const SIGNATURES_VERSION: u32 = 1;
+
+/// A pattern for the instruction loading a pointer, plus what is needed to follow it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignatureEntry {
+    pub name: String,
+    pub pattern: String,
+    /// Offset of the RIP-relative displacement from the start of the pattern.
+    pub displacement_offset: usize,
+    /// Distance from the start of the pattern to the end of the instruction.
+    pub instruction_length: usize,
+    /// Client builds this pattern is known to work on.
+    #[serde(default)]
+    pub known_builds: Vec<String>,
+}
+
+/// The patterns for every field, in the order they are tried.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignatureDatabase {
+    pub version: u32,
+    #[serde(default)]
+    pub player: Vec<SignatureEntry>,
+}
+
+/// The first pattern of a field that was found, with the addresses it resolved to.
+#[derive(Debug)]
+pub struct SignatureMatch<'a> {
+    pub entry: &'a SignatureEntry,
+    pub trace: PointerTrace,
+}
+
+/// Where a pattern matches in an executable on disk.
+#[derive(Debug)]
+pub struct OfflineMatch {
+    /// RVA of the start of the pattern.
+    pub rva: u32,
+    /// RVA of the global the instruction loads the pointer from. Its value is
+    /// only known at runtime, so the path can't be followed any further.
+    pub pointer_rva: Result<u32, PointerPathError>,
+}
+
+#[derive(Debug)]
+pub enum SignaturesError {
+    IOError { path: PathBuf, io_error: IoError },
+    ParseError { path: PathBuf, message: String },
+    UnsupportedVersion { version: u32 },
+    InvalidSignature { name: String, reason: String },
+    NoSignatures { field: String },
+}
+
+impl fmt::Display for SignaturesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignaturesError::IOError { path, io_error } => {
+                write!(f, "failed to read {}: {}", path.display(), io_error)
+            }
+            SignaturesError::ParseError { path, message } => {
+                write!(f, "failed to parse {}: {}", path.display(), message)
+            }
+            SignaturesError::UnsupportedVersion { version } => write!(
+                f,
+                "signature database version {} is not supported, the newest is {}",
+                version, SIGNATURES_VERSION
+            ),
+            SignaturesError::InvalidSignature { name, reason } => {
+                write!(f, "invalid signature \"{}\": {}", name, reason)
+            }
+            SignaturesError::NoSignatures { field } => {
+                write!(f, "no signatures defined for {}", field)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SignaturesError {}
+
+impl SignatureDatabase {
+    /// The signatures shipped with this release.
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN_SIGNATURES, Path::new("<builtin>")).expect("invalid builtin signatures")
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SignaturesError> {
+        let text = fs::read_to_string(path).map_err(|io_error| SignaturesError::IOError {
+            path: path.into(),
+            io_error,
+        })?;
+        Self::parse(&text, path)
+    }
+
+    /// Loads `signatures.toml` next to the executable if there is one, otherwise the builtin signatures.
+    pub fn load_default() -> Result<Self, SignaturesError> {
+        match default_path() {
+            Some(path) if path.exists() => Self::load(&path),
+            _ => Ok(Self::builtin()),
+        }
+    }
+
+    pub fn parse(text: &str, path: &Path) -> Result<Self, SignaturesError> {
+        let database: SignatureDatabase =
+            toml::from_str(text).map_err(|error| SignaturesError::ParseError {
+                path: path.into(),
+                message: error.to_string(),
+            })?;
+        database.validate()?;
+        Ok(database)
+    }
+
+    pub fn validate(&self) -> Result<(), SignaturesError> {
+        if self.version == 0 || self.version > SIGNATURES_VERSION {
+            return Err(SignaturesError::UnsupportedVersion {
+                version: self.version,
+            });
+        }
+        if self.player.is_empty() {
+            return Err(SignaturesError::NoSignatures {
+                field: "player".into(),
+            });
+        }
+
+        for (index, entry) in self.player.iter().enumerate() {
+            if self.player[..index]
+                .iter()
+                .any(|other| other.name == entry.name)
+            {
+                return Err(invalid(entry, "duplicate signature name"));
+            }
+            entry.validate()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn player_by_name(&self, name: &str) -> Option<&SignatureEntry> {
+        self.player.iter().find(|entry| entry.name == name)
+    }
+
+    /// Tries the player signatures in order and returns the first one found in `module`.
+    ///
+    /// Fails when the module can't be scanned at all, e.g. because it isn't loaded.
+    pub fn find_player(
+        &self,
+        process: &impl MemoryReader,
+        module: &str,
+    ) -> Result<Option<SignatureMatch<'_>>, Error> {
+        for entry in &self.player {
+            let trace = entry.player_path().trace(process, module);
+            // the first address is the one of the signature itself
+            if !trace.addresses.is_empty() {
+                return Ok(Some(SignatureMatch { entry, trace }));
+            }
+            // only a signature that isn't found moves on to the next one
+            if let Some(PointerPathError {
+                kind: PointerErrorKind::Error(error),
+                ..
+            }) = trace.error
+            {
+                return Err(error);
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl SignatureEntry {
+    /// Looks for the pattern in the executable sections of `pe` and follows the
+    /// RIP-relative operand, without a running client.
+    pub fn find_offline(&self, pe: &PeFile) -> Option<OfflineMatch> {
+        let signature: Signature = self.pattern.parse().ok()?;
+        let rva = pe.find_signature(&signature)?;
+
+        let image_base = pe.image_base as usize;
+        let static_steps = self.player_path().steps[..2].to_vec();
+        let pointer_rva = PointerPath::new(static_steps.clone())
+            .resolve_after(pe, 0, image_base + rva as usize)
+            .and_then(|address| {
+                // a displacement pointing outside of the image has no RVA
+                address
+                    .checked_sub(image_base)
+                    .and_then(|rva| u32::try_from(rva).ok())
+                    .ok_or_else(|| PointerPathError {
+                        step: 1,
+                        step_text: static_steps[1].to_string(),
+                        kind: PointerErrorKind::AddressOverflow,
+                    })
+            });
+        Some(OfflineMatch { rva, pointer_rva })
+    }
+
+    /// Path from the pattern to the player struct.
+    pub fn player_path(&self) -> PointerPath {
+        PointerPath::new(vec![
+            PointerStep::Sig(self.pattern.clone()),
+            PointerStep::RipRel {
+                displacement_offset: self.displacement_offset,
+                instruction_length: self.instruction_length,
+            },
+            PointerStep::Deref64,
+        ])
+    }
+
+    pub fn validate(&self) -> Result<(), SignaturesError> {
+        if self.name.trim().is_empty() {
+            return Err(invalid(self, "name is empty"));
+        }
+        if let Err(error) = self.pattern.parse::<Signature>() {
+            return Err(invalid(self, &error.to_string()));
+        }
+        // The displacement is a 32 bit value inside of the instruction
+        if self.displacement_offset + 4 > self.instruction_length {
+            return Err(invalid(
+                self,
+                "displacement_offset + 4 must not exceed instruction_length",
+            ));
+        }
+        if self.instruction_length > 15 {
+            return Err(invalid(
+                self,
+                "instruction_length is longer than any x86 instruction",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+fn invalid(entry: &SignatureEntry, reason: &str) -> SignaturesError {
+    SignaturesError::InvalidSignature {
+        name: entry.name.clone(),
+        reason: reason.into(),
+    }
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    Some(
+        std::env::current_exe()
+            .ok()?
+            .with_file_name(SIGNATURES_FILE_NAME),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<SignatureDatabase, SignaturesError> {
+        SignatureDatabase::parse(text, Path::new("test.toml"))
+    }
+
+    const DATABASE: &str = r#"
+version = 1
+
+[[player]]
+name = "new"
+pattern = "48 8B 0D ? ? ? ?"
+displacement_offset = 3
+instruction_length = 7
+known_builds = ["1.0.0.200"]
+
+[[player]]
+name = "old"
+pattern = "? 83 EC 28 ? 8B 05"
+displacement_offset = 0x07
+instruction_length = 11
+"#;
+
+    #[test]
+    fn finds_signatures_in_executable_files() {
+        let database = parse(DATABASE).unwrap();
+        // mov rcx, [rip + 0x1009] at the start of .text, loading the global at .data + 0x10
+        let mut code = vec![0x90; 0x10];
+        code[..7].copy_from_slice(&[0x48, 0x8B, 0x0D, 0x09, 0x10, 0x00, 0x00]);
+        let pe = PeFile::parse(crate::pe::tests::sample_pe(&code)).unwrap();
+
+        let found = database.player[0].find_offline(&pe).unwrap();
+        assert_eq!(found.rva, crate::pe::tests::TEXT_RVA);
+        assert_eq!(found.pointer_rva.unwrap(), 0x2010);
+        assert_eq!(pe.section_at_rva(0x2010).unwrap().name, ".data");
+
+        assert!(database.player[1].find_offline(&pe).is_none());
+    }
+
+    #[test]
+    fn rejects_pointers_outside_of_the_image() {
+        let database = parse(DATABASE).unwrap();
+        // mov rcx, [rip - 0x2000], below the image base
+        let mut code = vec![0x90; 0x10];
+        code[..7].copy_from_slice(&[0x48, 0x8B, 0x0D, 0x00, 0xE0, 0xFF, 0xFF]);
+        let pe = PeFile::parse(crate::pe::tests::sample_pe(&code)).unwrap();
+
+        let found = database.player[0].find_offline(&pe).unwrap();
+        assert!(matches!(
+            found.pointer_rva,
+            Err(PointerPathError {
+                step: 1,
+                kind: PointerErrorKind::AddressOverflow,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn builtin_database_is_valid() {
+        let database = SignatureDatabase::builtin();
+        assert_eq!(database.version, SIGNATURES_VERSION);
+        assert_eq!(database.player[0].displacement_offset, 0x07);
+        assert_eq!(database.player[0].instruction_length, 11);
+    }
+
+    #[test]
+    fn keeps_signatures_in_order() {
+        let database = parse(DATABASE).unwrap();
+        let names: Vec<&str> = database.player.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["new", "old"]);
+        assert_eq!(database.player[0].known_builds, ["1.0.0.200"]);
+        assert!(database.player[1].known_builds.is_empty());
+        assert_eq!(
+            database.player_by_name("old").unwrap().pattern,
+            "? 83 EC 28 ? 8B 05"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_databases() {
+        let error = parse(&DATABASE.replace("version = 1", "version = 2")).unwrap_err();
+        assert!(matches!(
+            error,
+            SignaturesError::UnsupportedVersion { version: 2 }
+        ));
+
+        let error = parse(&DATABASE.replace("48 8B 0D", "48 8X 0D")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid signature \"new\": invalid byte \"8X\" at index 1"
+        );
+
+        let error = parse(&DATABASE.replace("instruction_length = 7", "instruction_length = 6"))
+            .unwrap_err();
+        assert!(
+            matches!(error, SignaturesError::InvalidSignature { ref name, .. } if name == "new")
+        );
+
+        let error = parse(&DATABASE.replace("\"old\"", "\"new\"")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid signature \"new\": duplicate signature name"
+        );
+
+        assert!(matches!(
+            parse("version = 1").unwrap_err(),
+            SignaturesError::NoSignatures { .. }
+        ));
+    }
+}
@@ -0,0 +1,268 @@
+//! Multibox slot numbers, so every character keeps its number across restarts and
+//! when it logs in on another client.
+
+use std::fmt;
+use std::fs;
+use std::io::Error as IoError;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::APP_DIR_NAME;
+
+pub const SLOTS_FILE_NAME: &str = "slots.toml";
+/// Days after which a character that wasn't seen gives its slot to new characters.
+pub const STALE_AFTER_DAYS: u64 = 30;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotEntry {
+    pub character: String,
+    pub slot: u32,
+    /// Day the character was last seen, counted from the Unix epoch. `None` for
+    /// entries written before it was recorded, those never go stale.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<u64>,
+}
+
+impl SlotEntry {
+    fn is_stale(&self, today: u64) -> bool {
+        self.last_seen
+            .is_some_and(|day| today.saturating_sub(day) >= STALE_AFTER_DAYS)
+    }
+}
+
+/// The slot of every character seen so far.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotAssignments {
+    #[serde(rename = "slot", default)]
+    pub entries: Vec<SlotEntry>,
+    /// Whether there are entries that haven't been saved yet.
+    #[serde(skip)]
+    changed: bool,
+}
+
+#[derive(Debug)]
+pub enum SlotsError {
+    IOError {
+        path: PathBuf,
+        io_error: IoError,
+    },
+    ParseError {
+        path: PathBuf,
+        message: String,
+    },
+    /// The file contents couldn't be turned into TOML to save them.
+    SerializeError {
+        path: PathBuf,
+        message: String,
+    },
+}
+
+impl fmt::Display for SlotsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SlotsError::IOError { path, io_error } => {
+                write!(f, "failed to access {}: {}", path.display(), io_error)
+            }
+            SlotsError::ParseError { path, message } => {
+                write!(f, "failed to parse {}: {}", path.display(), message)
+            }
+            SlotsError::SerializeError { path, message } => {
+                write!(f, "failed to write {}: {}", path.display(), message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SlotsError {}
+
+impl SlotAssignments {
+    pub fn load(path: &Path) -> Result<Self, SlotsError> {
+        let text = fs::read_to_string(path).map_err(|io_error| SlotsError::IOError {
+            path: path.into(),
+            io_error,
+        })?;
+        toml::from_str(&text).map_err(|error| SlotsError::ParseError {
+            path: path.into(),
+            message: error.to_string(),
+        })
+    }
+
+    /// Loads the slots, starting over without any when the file is missing or broken.
+    pub fn load_or_default(path: &Path) -> Self {
+        Self::load(path).unwrap_or_default()
+    }
+
+    /// Saves the slots if characters were assigned one since they were loaded.
+    pub fn save_if_changed(&mut self, path: &Path) -> Result<(), SlotsError> {
+        if !self.changed {
+            return Ok(());
+        }
+        self.save(path)?;
+        self.changed = false;
+        Ok(())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SlotsError> {
+        let text = toml::to_string(self).map_err(|error| SlotsError::SerializeError {
+            path: path.into(),
+            message: error.to_string(),
+        })?;
+        let io_error = |io_error| SlotsError::IOError {
+            path: path.into(),
+            io_error,
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(io_error)?;
+        }
+        fs::write(path, text).map_err(io_error)
+    }
+
+    pub fn get(&self, character: &str) -> Option<u32> {
+        self.entries
+            .iter()
+            .find(|entry| entry.character == character)
+            .map(|entry| entry.slot)
+    }
+
+    /// The slot of `character` seen on `today` (see `today`), giving it the lowest
+    /// slot that is free or held by a character not seen for `STALE_AFTER_DAYS`.
+    /// `None` for an empty name, e.g. on the character select screen.
+    pub fn assign(&mut self, character: &str, today: u64) -> Option<u32> {
+        if character.is_empty() {
+            return None;
+        }
+        if let Some(entry) = self
+            .entries
+            .iter_mut()
+            .find(|entry| entry.character == character)
+        {
+            if entry.last_seen != Some(today) {
+                entry.last_seen = Some(today);
+                self.changed = true;
+            }
+            return Some(entry.slot);
+        }
+
+        let slot = (1..)
+            .find(|&slot| {
+                self.entries
+                    .iter()
+                    .all(|entry| entry.slot != slot || entry.is_stale(today))
+            })
+            .expect("free slot");
+        self.entries.retain(|entry| entry.slot != slot);
+        self.entries.push(SlotEntry {
+            character: character.into(),
+            slot,
+            last_seen: Some(today),
+        });
+        self.changed = true;
+        Some(slot)
+    }
+
+    /// Frees the slot of `character`, `false` if it had none.
+    pub fn release(&mut self, character: &str) -> bool {
+        let count = self.entries.len();
+        self.entries.retain(|entry| entry.character != character);
+        self.changed |= self.entries.len() != count;
+        self.entries.len() != count
+    }
+
+    /// Frees all slots, the characters get new ones as they are seen.
+    pub fn clear(&mut self) {
+        self.changed |= !self.entries.is_empty();
+        self.entries.clear();
+    }
+}
+
+/// The current day for `SlotAssignments::assign`.
+pub fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() / 86_400)
+}
+
+/// `slots.toml` in the user's data directory, the directory of the executable may
+/// not be writable.
+pub fn default_path() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join(APP_DIR_NAME).join(SLOTS_FILE_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 20_000;
+
+    #[test]
+    fn assigns_the_lowest_free_slot() {
+        let mut slots = SlotAssignments::default();
+        slots.entries.push(SlotEntry {
+            character: "Buffer".into(),
+            slot: 2,
+            last_seen: None,
+        });
+
+        assert_eq!(slots.assign("Xikeon", DAY), Some(1));
+        assert_eq!(slots.assign("Seller", DAY), Some(3));
+        assert_eq!(slots.assign("Buffer", DAY), Some(2));
+        assert_eq!(slots.assign("Xikeon", DAY), Some(1));
+        assert_eq!(slots.get("Other"), None);
+        // the character select screen has no name
+        assert_eq!(slots.assign("", DAY), None);
+        assert_eq!(slots.entries.len(), 3);
+    }
+
+    #[test]
+    fn reuses_slots_of_characters_not_seen_for_a_while() {
+        let mut slots = SlotAssignments::default();
+        slots.assign("Xikeon", DAY);
+        slots.assign("OldAlt", DAY);
+        slots.assign("Buffer", DAY + 10);
+
+        let later = DAY + STALE_AFTER_DAYS;
+        assert_eq!(slots.assign("Xikeon", later), Some(1));
+        // OldAlt wasn't seen for STALE_AFTER_DAYS, Buffer was seen more recently
+        assert_eq!(slots.assign("Reroll", later), Some(2));
+        assert_eq!(slots.get("OldAlt"), None);
+        assert_eq!(slots.assign("Seller", later), Some(4));
+
+        assert!(slots.release("Buffer"));
+        assert!(!slots.release("Buffer"));
+        assert_eq!(slots.assign("Crafter", later), Some(3));
+
+        slots.changed = false;
+        slots.clear();
+        assert!(slots.changed && slots.entries.is_empty());
+    }
+
+    #[test]
+    fn saves_and_loads_slots() {
+        let dir = std::env::temp_dir().join(format!("slots_{}", std::process::id()));
+        let path = dir.join(SLOTS_FILE_NAME);
+        let mut slots = SlotAssignments::default();
+        slots.save_if_changed(&path).unwrap();
+        assert!(!path.exists());
+
+        slots.assign("Xikeon", DAY);
+        slots.assign("Buffer", DAY);
+        slots.save_if_changed(&path).unwrap();
+        assert!(!slots.changed);
+        // seeing the characters again on the same day doesn't need a save
+        slots.assign("Xikeon", DAY);
+        assert!(!slots.changed);
+
+        let loaded = SlotAssignments::load(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(loaded.entries, slots.entries);
+        assert_eq!(loaded.get("Buffer"), Some(2));
+        assert!(SlotAssignments::load_or_default(&path).entries.is_empty());
+
+        // files written before the day was recorded
+        let old: SlotAssignments =
+            toml::from_str("[[slot]]\ncharacter = \"Xikeon\"\nslot = 1\n").unwrap();
+        assert_eq!(old.entries[0].last_seen, None);
+    }
+}
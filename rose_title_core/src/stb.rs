@@ -0,0 +1,299 @@
+//! The data tables of the client: STB files hold rows of text cells and STL files
+//! the strings the STB rows refer to, in every language the client ships.
+
+use std::fmt;
+use std::fs;
+use std::io::Error as IoError;
+use std::path::{Path, PathBuf};
+
+use crate::encoding::TextEncoding;
+
+/// Magics of the STL files, the item and quest tables also store descriptions
+/// after each text but those aren't needed here.
+const STL_MAGICS: [&[u8]; 3] = [b"NRST01", b"ITST01", b"QEST01"];
+
+#[derive(Debug)]
+pub enum StbError {
+    IOError { path: PathBuf, io_error: IoError },
+    InvalidHeader { reason: String },
+    Truncated { offset: usize },
+}
+
+impl fmt::Display for StbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StbError::IOError { path, io_error } => {
+                write!(f, "failed to read {}: {}", path.display(), io_error)
+            }
+            StbError::InvalidHeader { reason } => write!(f, "invalid data table: {}", reason),
+            StbError::Truncated { offset } => {
+                write!(f, "data table ends before offset {:#x}", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StbError {}
+
+/// An STB table, the cells as stored in the file without the header row and the
+/// row name column.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StbFile {
+    rows: Vec<Vec<Vec<u8>>>,
+}
+
+impl StbFile {
+    pub fn load(path: &Path) -> Result<Self, StbError> {
+        Self::parse(&read_file(path)?)
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self, StbError> {
+        match bytes_at(data, 0, 4)? {
+            b"STB1" => {}
+            magic => {
+                return Err(invalid(&format!(
+                    "unknown STB magic {:?}",
+                    String::from_utf8_lossy(magic)
+                )))
+            }
+        }
+        let data_offset = u32_at(data, 4)? as usize;
+        // both counts include the header row and the row name column
+        let row_count = (u32_at(data, 8)? as usize).saturating_sub(1);
+        let column_count = (u32_at(data, 12)? as usize).saturating_sub(1);
+
+        // the column widths and names in between are only used by editors
+        let mut offset = data_offset;
+        let rows = (0..row_count)
+            .map(|_| {
+                (0..column_count)
+                    .map(|_| {
+                        let length = u16_at(data, offset)? as usize;
+                        let cell = bytes_at(data, offset + 2, length)?;
+                        offset += 2 + length;
+                        Ok(cell.to_vec())
+                    })
+                    .collect::<Result<Vec<_>, StbError>>()
+            })
+            .collect::<Result<Vec<_>, StbError>>()?;
+        Ok(StbFile { rows })
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.rows.first().map_or(0, |row| row.len())
+    }
+
+    /// The bytes of a cell, `None` outside of the table.
+    pub fn cell(&self, row: usize, column: usize) -> Option<&[u8]> {
+        Some(self.rows.get(row)?.get(column)?.as_slice())
+    }
+
+    /// A cell decoded with `encoding`, invalid characters are replaced.
+    pub fn text(&self, row: usize, column: usize, encoding: TextEncoding) -> Option<String> {
+        Some(decode_lossy(self.cell(row, column)?, encoding))
+    }
+}
+
+/// An STL string table: texts by key, one set for every language.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StlFile {
+    /// The key and id of every entry.
+    pub entries: Vec<(String, u32)>,
+    /// The text of every entry, by language.
+    texts: Vec<Vec<Vec<u8>>>,
+}
+
+impl StlFile {
+    pub fn load(path: &Path) -> Result<Self, StbError> {
+        Self::parse(&read_file(path)?)
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self, StbError> {
+        let (magic, mut offset) = var_bytes_at(data, 0)?;
+        if !STL_MAGICS.contains(&magic) {
+            return Err(invalid(&format!(
+                "unknown STL magic {:?}",
+                String::from_utf8_lossy(magic)
+            )));
+        }
+
+        let entry_count = u32_at(data, offset)? as usize;
+        offset += 4;
+        let entries = (0..entry_count)
+            .map(|_| {
+                let (key, next) = var_bytes_at(data, offset)?;
+                let id = u32_at(data, next)?;
+                offset = next + 4;
+                Ok((String::from_utf8_lossy(key).into_owned(), id))
+            })
+            .collect::<Result<Vec<_>, StbError>>()?;
+
+        let language_count = u32_at(data, offset)? as usize;
+        let texts = (0..language_count)
+            .map(|language| {
+                let table = u32_at(data, offset + 4 + language * 4)? as usize;
+                (0..entry_count)
+                    .map(|entry| {
+                        let text = u32_at(data, table + entry * 4)? as usize;
+                        Ok(var_bytes_at(data, text)?.0.to_vec())
+                    })
+                    .collect::<Result<Vec<_>, StbError>>()
+            })
+            .collect::<Result<Vec<_>, StbError>>()?;
+        Ok(StlFile { entries, texts })
+    }
+
+    pub fn language_count(&self) -> usize {
+        self.texts.len()
+    }
+
+    /// The text of the entry with `key` in `language`, invalid characters replaced.
+    pub fn text(&self, key: &str, language: usize, encoding: TextEncoding) -> Option<String> {
+        let entry = self.entries.iter().position(|(entry, _)| entry == key)?;
+        self.entry_text(entry, language, encoding)
+    }
+
+    /// Like `text` for the entry with `id`.
+    pub fn text_by_id(&self, id: u32, language: usize, encoding: TextEncoding) -> Option<String> {
+        let entry = self.entries.iter().position(|&(_, entry)| entry == id)?;
+        self.entry_text(entry, language, encoding)
+    }
+
+    fn entry_text(&self, entry: usize, language: usize, encoding: TextEncoding) -> Option<String> {
+        let text = self.texts.get(language)?.get(entry)?;
+        Some(decode_lossy(text, encoding))
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, StbError> {
+    fs::read(path).map_err(|io_error| StbError::IOError {
+        path: path.into(),
+        io_error,
+    })
+}
+
+fn decode_lossy(bytes: &[u8], encoding: TextEncoding) -> String {
+    encoding
+        .decode(bytes)
+        .unwrap_or_else(|| String::from_utf8_lossy(bytes).into_owned())
+}
+
+fn invalid(reason: &str) -> StbError {
+    StbError::InvalidHeader {
+        reason: reason.into(),
+    }
+}
+
+fn bytes_at(data: &[u8], offset: usize, length: usize) -> Result<&[u8], StbError> {
+    data.get(offset..offset + length)
+        .ok_or(StbError::Truncated {
+            offset: offset + length,
+        })
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Result<u16, StbError> {
+    Ok(u16::from_le_bytes(
+        bytes_at(data, offset, 2)?.try_into().unwrap(),
+    ))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32, StbError> {
+    Ok(u32::from_le_bytes(
+        bytes_at(data, offset, 4)?.try_into().unwrap(),
+    ))
+}
+
+/// Bytes prefixed with their length in 7 bit groups, and the offset after them.
+fn var_bytes_at(data: &[u8], mut offset: usize) -> Result<(&[u8], usize), StbError> {
+    let mut length = 0;
+    for shift in (0..35).step_by(7) {
+        let byte = *bytes_at(data, offset, 1)?.first().unwrap();
+        offset += 1;
+        length |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return Ok((bytes_at(data, offset, length)?, offset + length));
+        }
+    }
+    Err(invalid("string length is too long"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLASS_LIST: &[u8] = include_bytes!("../tests/data/3DDATA/STB/LIST_CLASS.STB");
+    const CLASS_NAMES: &[u8] = include_bytes!("../tests/data/3DDATA/STB/LIST_CLASS_S.STL");
+
+    #[test]
+    fn parses_stb_files() {
+        let stb = StbFile::parse(CLASS_LIST).unwrap();
+        assert_eq!(stb.row_count(), 133);
+        assert_eq!(stb.column_count(), 3);
+        assert_eq!(stb.cell(111, 0), Some(&b"Soldier"[..]));
+        assert_eq!(
+            stb.text(131, 2, TextEncoding::Utf8).as_deref(),
+            Some("CLASS_131")
+        );
+        assert_eq!(stb.cell(110, 2), Some(&b""[..]));
+        assert_eq!(stb.cell(133, 0), None);
+        assert_eq!(stb.cell(0, 3), None);
+    }
+
+    #[test]
+    fn parses_stl_files() {
+        let stl = StlFile::parse(CLASS_NAMES).unwrap();
+        assert_eq!(stl.language_count(), 2);
+        assert_eq!(stl.entries.len(), 5);
+        assert_eq!(stl.entries[1], ("CLASS_111".into(), 111));
+        assert_eq!(
+            stl.text("CLASS_121", 1, TextEncoding::Utf8).as_deref(),
+            Some("Knight")
+        );
+        assert_eq!(
+            stl.text("CLASS_131", 0, TextEncoding::Utf8).as_deref(),
+            Some("버서커")
+        );
+        // longer than 127 bytes, so its length takes two bytes
+        assert_eq!(
+            stl.text_by_id(132, 1, TextEncoding::Utf8)
+                .map(|text| text.len()),
+            Some(164)
+        );
+        assert_eq!(stl.text("CLASS_121", 2, TextEncoding::Utf8), None);
+        assert_eq!(stl.text("CLASS_999", 1, TextEncoding::Utf8), None);
+    }
+
+    #[test]
+    fn decodes_invalid_text_lossily() {
+        assert_eq!(decode_lossy(b"Ren\xE9", TextEncoding::Cp1252), "René");
+        assert_eq!(decode_lossy(b"Ren\xE9", TextEncoding::Utf8), "Ren\u{FFFD}");
+    }
+
+    #[test]
+    fn rejects_broken_files() {
+        assert!(matches!(
+            StbFile::parse(b"STB0\0\0\0\0"),
+            Err(StbError::InvalidHeader { .. })
+        ));
+        assert!(matches!(
+            StbFile::parse(&CLASS_LIST[..CLASS_LIST.len() - 1]),
+            Err(StbError::Truncated { .. })
+        ));
+        assert!(matches!(
+            StlFile::parse(b"\x06NRST02"),
+            Err(StbError::InvalidHeader { .. })
+        ));
+        assert!(matches!(
+            StlFile::parse(&CLASS_NAMES[..CLASS_NAMES.len() - 1]),
+            Err(StbError::Truncated { .. })
+        ));
+        assert!(matches!(
+            StbFile::load(Path::new("/nonexistent/LIST_CLASS.STB")),
+            Err(StbError::IOError { .. })
+        ));
+    }
+}
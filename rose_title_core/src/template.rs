@@ -0,0 +1,441 @@
+//! Templates of window titles, e.g. `{name} [{job_short}] Lv{level} {hp_pct}%`.
+//!
+//! - `{name}` is replaced by the variable, `{name|none}` falls back to `none` when
+//!   the variable is missing or empty
+//! - `{name:<12}`, `{name:>12}` and `{name:^12}` pad to 12 characters aligned
+//!   left, right or centered, `{name:.8}` truncates to 8 characters and
+//!   `{name:<12.8}` does both
+//! - `{?party}..{/}` only shows its content when the variable is there,
+//!   `{!party}..{/}` only when it is missing
+//! - `{{` and `}}` are literal braces
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+}
+
+/// Padding and truncation of a variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Format {
+    pub alignment: Alignment,
+    pub width: usize,
+    pub max_length: Option<usize>,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Format {
+            alignment: Alignment::Left,
+            width: 0,
+            max_length: None,
+        }
+    }
+}
+
+impl Format {
+    fn apply(&self, value: &str) -> String {
+        let value: String = match self.max_length {
+            Some(max_length) => value.chars().take(max_length).collect(),
+            None => value.into(),
+        };
+        let padding = self.width.saturating_sub(value.chars().count());
+        let (left, right) = match self.alignment {
+            Alignment::Left => (0, padding),
+            Alignment::Center => (padding / 2, padding - padding / 2),
+            Alignment::Right => (padding, 0),
+        };
+        format!("{}{}{}", " ".repeat(left), value, " ".repeat(right))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Variable {
+        name: String,
+        format: Format,
+        fallback: Option<String>,
+    },
+    /// Shows `parts` when the variable is there, or when it's missing if `negated`.
+    Conditional {
+        name: String,
+        negated: bool,
+        parts: Vec<Part>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateErrorKind {
+    UnclosedPlaceholder,
+    UnexpectedBrace,
+    MissingVariable,
+    UnknownVariable { name: String, expected: Vec<String> },
+    InvalidFormat { format: String },
+    UnclosedConditional { name: String },
+    UnmatchedEnd,
+}
+
+impl fmt::Display for TemplateErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateErrorKind::UnclosedPlaceholder => {
+                write!(f, "`{{` is never closed, write `{{{{` for a literal brace")
+            }
+            TemplateErrorKind::UnexpectedBrace => {
+                write!(f, "unexpected `}}`, write `}}}}` for a literal brace")
+            }
+            TemplateErrorKind::MissingVariable => write!(f, "placeholder without a variable"),
+            TemplateErrorKind::UnknownVariable { name, expected } => write!(
+                f,
+                "unknown variable `{}`, expected one of {}",
+                name,
+                expected.join(", ")
+            ),
+            TemplateErrorKind::InvalidFormat { format } => write!(
+                f,
+                "invalid format `{}`, expected an alignment (<, ^ or >), a width and a .max length like `<12.8`",
+                format
+            ),
+            TemplateErrorKind::UnclosedConditional { name } => {
+                write!(f, "`{{?{}}}` is never closed with `{{/}}`", name)
+            }
+            TemplateErrorKind::UnmatchedEnd => write!(f, "`{{/}}` without an open `{{?..}}`"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError {
+    /// Column of the character the error is at, starting at 1.
+    pub column: usize,
+    pub kind: TemplateErrorKind,
+}
+
+impl TemplateError {
+    /// A `^` under the column of the error, to print below the template.
+    pub fn marker(&self) -> String {
+        format!("{}^", " ".repeat(self.column.saturating_sub(1)))
+    }
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.kind, self.column)
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// A parsed template, see the module documentation for the syntax.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    source: String,
+    parts: Vec<Part>,
+}
+
+/// An open `{?..}` and the parts before it.
+struct OpenConditional {
+    name: String,
+    negated: bool,
+    column: usize,
+    outer: Vec<Part>,
+}
+
+impl Template {
+    /// Parses `source`, only allowing the names in `variables`.
+    pub fn parse(source: &str, variables: &[&str]) -> Result<Template, TemplateError> {
+        let chars: Vec<char> = source.chars().collect();
+        let mut parts = vec![];
+        let mut open: Vec<OpenConditional> = vec![];
+        let mut text = String::new();
+        let mut index = 0;
+
+        while index < chars.len() {
+            let column = index + 1;
+            match (chars[index], chars.get(index + 1)) {
+                ('{', Some('{')) | ('}', Some('}')) => {
+                    text.push(chars[index]);
+                    index += 2;
+                    continue;
+                }
+                ('}', _) => {
+                    return Err(TemplateError {
+                        column,
+                        kind: TemplateErrorKind::UnexpectedBrace,
+                    })
+                }
+                ('{', _) => {}
+                (c, _) => {
+                    text.push(c);
+                    index += 1;
+                    continue;
+                }
+            }
+
+            let end = chars[index + 1..]
+                .iter()
+                .position(|&c| c == '}' || c == '{')
+                .map(|offset| index + 1 + offset)
+                .filter(|&end| chars[end] == '}')
+                .ok_or(TemplateError {
+                    column,
+                    kind: TemplateErrorKind::UnclosedPlaceholder,
+                })?;
+            let placeholder: String = chars[index + 1..end].iter().collect();
+            index = end + 1;
+
+            if !text.is_empty() {
+                parts.push(Part::Text(std::mem::take(&mut text)));
+            }
+
+            if placeholder == "/" {
+                let conditional = open.pop().ok_or(TemplateError {
+                    column,
+                    kind: TemplateErrorKind::UnmatchedEnd,
+                })?;
+                let body = std::mem::replace(&mut parts, conditional.outer);
+                parts.push(Part::Conditional {
+                    name: conditional.name,
+                    negated: conditional.negated,
+                    parts: body,
+                });
+            } else if let Some(negated) = match placeholder.chars().next() {
+                Some('?') => Some(false),
+                Some('!') => Some(true),
+                _ => None,
+            } {
+                let name = &placeholder[1..];
+                check_variable(name, variables, column)?;
+                open.push(OpenConditional {
+                    name: name.into(),
+                    negated,
+                    column,
+                    outer: std::mem::take(&mut parts),
+                });
+            } else {
+                let (head, fallback) = match placeholder.split_once('|') {
+                    Some((head, fallback)) => (head, Some(fallback.to_string())),
+                    None => (placeholder.as_str(), None),
+                };
+                let (name, format) = match head.split_once(':') {
+                    Some((name, format)) => (
+                        name,
+                        parse_format(format).ok_or_else(|| TemplateError {
+                            column,
+                            kind: TemplateErrorKind::InvalidFormat {
+                                format: format.into(),
+                            },
+                        })?,
+                    ),
+                    None => (head, Format::default()),
+                };
+                check_variable(name, variables, column)?;
+                parts.push(Part::Variable {
+                    name: name.into(),
+                    format,
+                    fallback,
+                });
+            }
+        }
+
+        if let Some(conditional) = open.pop() {
+            return Err(TemplateError {
+                column: conditional.column,
+                kind: TemplateErrorKind::UnclosedConditional {
+                    name: conditional.name,
+                },
+            });
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(Template {
+            source: source.into(),
+            parts,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Fills in the variables, `lookup` returns `None` for missing ones.
+    pub fn render(&self, lookup: impl Fn(&str) -> Option<String>) -> String {
+        let mut output = String::new();
+        render_parts(&self.parts, &lookup, &mut output);
+        output
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+fn check_variable(name: &str, variables: &[&str], column: usize) -> Result<(), TemplateError> {
+    if name.is_empty() {
+        return Err(TemplateError {
+            column,
+            kind: TemplateErrorKind::MissingVariable,
+        });
+    }
+    if !variables.contains(&name) {
+        return Err(TemplateError {
+            column,
+            kind: TemplateErrorKind::UnknownVariable {
+                name: name.into(),
+                expected: variables.iter().map(|&name| name.into()).collect(),
+            },
+        });
+    }
+    Ok(())
+}
+
+/// Parses `[<^>][width][.max_length]`.
+fn parse_format(text: &str) -> Option<Format> {
+    let (alignment, rest) = match text.chars().next() {
+        Some('<') => (Alignment::Left, &text[1..]),
+        Some('^') => (Alignment::Center, &text[1..]),
+        Some('>') => (Alignment::Right, &text[1..]),
+        _ => (Alignment::Left, text),
+    };
+    let (width, max_length) = match rest.split_once('.') {
+        Some((width, max_length)) => (width, Some(max_length.parse().ok()?)),
+        None => (rest, None),
+    };
+    let width = match width {
+        "" => 0,
+        width => width.parse().ok()?,
+    };
+    Some(Format {
+        alignment,
+        width,
+        max_length,
+    })
+}
+
+fn render_parts(parts: &[Part], lookup: &impl Fn(&str) -> Option<String>, output: &mut String) {
+    for part in parts {
+        match part {
+            Part::Text(text) => output.push_str(text),
+            Part::Variable {
+                name,
+                format,
+                fallback,
+            } => {
+                let value = present(lookup, name).or_else(|| fallback.clone());
+                output.push_str(&format.apply(value.as_deref().unwrap_or_default()));
+            }
+            Part::Conditional {
+                name,
+                negated,
+                parts,
+            } => {
+                if present(lookup, name).is_some() != *negated {
+                    render_parts(parts, lookup, output);
+                }
+            }
+        }
+    }
+}
+
+/// The value of the variable unless it's missing or empty.
+fn present(lookup: &impl Fn(&str) -> Option<String>, name: &str) -> Option<String> {
+    lookup(name).filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VARIABLES: &[&str] = &["name", "job", "level", "party"];
+
+    fn render(source: &str, party: Option<&str>) -> String {
+        Template::parse(source, VARIABLES)
+            .unwrap()
+            .render(|name| match name {
+                "name" => Some("Xikeon".into()),
+                "job" => Some("Mage".into()),
+                "level" => Some("90".into()),
+                "party" => party.map(String::from),
+                _ => None,
+            })
+    }
+
+    fn error(source: &str) -> TemplateError {
+        Template::parse(source, VARIABLES).unwrap_err()
+    }
+
+    #[test]
+    fn renders_variables_and_text() {
+        assert_eq!(
+            render("{name} [{job}] Lv{level}", None),
+            "Xikeon [Mage] Lv90"
+        );
+        assert_eq!(render("{{{name}}}", None), "{Xikeon}");
+        assert_eq!(render("", None), "");
+    }
+
+    #[test]
+    fn renders_conditionals_and_fallbacks() {
+        let source = "{name}{?party} (party of {party}){/}{!party} solo{/}";
+        assert_eq!(render(source, Some("3")), "Xikeon (party of 3)");
+        assert_eq!(render(source, None), "Xikeon solo");
+        assert_eq!(render("{party|-}", None), "-");
+        assert_eq!(render("{party|-}", Some("")), "-");
+        assert_eq!(render("{?name}{?party}{party}{/}!{/}", None), "!");
+    }
+
+    #[test]
+    fn pads_and_truncates() {
+        assert_eq!(render("[{name:<8}]", None), "[Xikeon  ]");
+        assert_eq!(render("[{name:>8}]", None), "[  Xikeon]");
+        assert_eq!(render("[{name:^9}]", None), "[ Xikeon  ]");
+        assert_eq!(render("[{name:.3}]", None), "[Xik]");
+        assert_eq!(render("[{name:>5.3}]", None), "[  Xik]");
+        assert_eq!(render("[{party:3|-}]", None), "[-  ]");
+    }
+
+    #[test]
+    fn reports_errors_with_columns() {
+        assert_eq!(
+            error("Lv{lvl}"),
+            TemplateError {
+                column: 3,
+                kind: TemplateErrorKind::UnknownVariable {
+                    name: "lvl".into(),
+                    expected: VARIABLES.iter().map(|&name| name.into()).collect(),
+                }
+            }
+        );
+        assert_eq!(
+            error("Lv{lvl}").to_string(),
+            "unknown variable `lvl`, expected one of name, job, level, party at column 3"
+        );
+        assert_eq!(error("{name").kind, TemplateErrorKind::UnclosedPlaceholder);
+        assert_eq!(error("{na{me}").column, 1);
+        assert_eq!(error("a}b").column, 2);
+        assert_eq!(error("a}b").marker(), " ^");
+        assert_eq!(error("{}").kind, TemplateErrorKind::MissingVariable);
+        assert_eq!(
+            error("{name:x}").to_string(),
+            "invalid format `x`, expected an alignment (<, ^ or >), a width and a .max length like `<12.8` at column 1"
+        );
+        assert_eq!(
+            error("x {?party}y"),
+            TemplateError {
+                column: 3,
+                kind: TemplateErrorKind::UnclosedConditional {
+                    name: "party".into()
+                }
+            }
+        );
+        assert_eq!(error("{name}{/}").kind, TemplateErrorKind::UnmatchedEnd);
+    }
+}
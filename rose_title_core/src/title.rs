@@ -0,0 +1,228 @@
+use crate::character::Character;
+use crate::helpers::job_name;
+use crate::jobs::JobNames;
+use crate::template::{Template, TemplateError};
+
+/// The variables title templates can use.
+pub const TITLE_VARIABLES: &[&str] = &[
+    "name",
+    "job",
+    "job_short",
+    "job_id",
+    "level",
+    "hp",
+    "max_hp",
+    "hp_pct",
+    "mp",
+    "max_mp",
+    "mp_pct",
+    "zone",
+    "zuly",
+    "party",
+    "alias",
+    "slot",
+];
+
+/// Which parts of the character end up in the window title, the preset template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TitleOptions {
+    pub show_username: bool,
+    pub show_job: bool,
+    pub show_level: bool,
+    /// Current and maximum HP.
+    pub show_hp: bool,
+    /// Current and maximum MP.
+    pub show_mp: bool,
+    pub show_zone: bool,
+    pub show_zuly: bool,
+}
+
+impl Default for TitleOptions {
+    fn default() -> Self {
+        TitleOptions {
+            show_username: true,
+            show_job: true,
+            show_level: false,
+            show_hp: false,
+            show_mp: false,
+            show_zone: false,
+            show_zuly: false,
+        }
+    }
+}
+
+impl TitleOptions {
+    /// The template joining the selected parts with " - ".
+    pub fn template_source(&self) -> String {
+        let parts = [
+            (self.show_username, "{name}{?alias} ({alias}){/}"),
+            (self.show_job, "{job|Unknown}"),
+            (self.show_level, "Lv {level|?}"),
+            (self.show_hp, "HP {hp|?}/{max_hp|?}"),
+            (self.show_mp, "MP {mp|?}/{max_mp|?}"),
+            (self.show_zone, "Zone {zone|?}"),
+            (self.show_zuly, "{zuly|?} zuly"),
+        ];
+        parts
+            .iter()
+            .filter(|(show, _)| *show)
+            .map(|(_, part)| *part)
+            .collect::<Vec<&str>>()
+            .join(" - ")
+    }
+
+    pub fn template(&self) -> Template {
+        parse_title_template(&self.template_source()).expect("invalid preset template")
+    }
+}
+
+/// Parses a title template using the `TITLE_VARIABLES`.
+pub fn parse_title_template(source: &str) -> Result<Template, TemplateError> {
+    Template::parse(source, TITLE_VARIABLES)
+}
+
+/// Value of one of the `TITLE_VARIABLES`, `None` when the character doesn't have it.
+///
+/// The `alias` and `slot` don't come from the character, see `RuleSet::format_title`.
+pub fn title_variable(character: &Character, job_names: &JobNames, name: &str) -> Option<String> {
+    let value = match name {
+        "name" => character.name.clone(),
+        "job" => job_name(job_names, character.job_id)?,
+        "job_short" => job_name(job_names, character.job_id)?
+            .chars()
+            .take(3)
+            .collect::<String>()
+            .to_uppercase(),
+        "job_id" => character.job_id.to_string(),
+        "level" => character.level?.to_string(),
+        "hp" => character.hp?.to_string(),
+        "max_hp" => character.max_hp?.to_string(),
+        "hp_pct" => character.hp_percent()?.to_string(),
+        "mp" => character.mp?.to_string(),
+        "max_mp" => character.max_mp?.to_string(),
+        "mp_pct" => character.mp_percent()?.to_string(),
+        "zone" => character.zone?.to_string(),
+        "zuly" => character.zuly?.to_string(),
+        "party" if character.in_party()? => character.party_members?.to_string(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+pub fn format_title(character: &Character, job_names: &JobNames, template: &Template) -> String {
+    template.render(|name| title_variable(character, job_names, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_variable_has_a_value() {
+        let character = Character {
+            name: "Xikeon".into(),
+            job_id: 221,
+            level: Some(90),
+            hp: Some(50),
+            max_hp: Some(100),
+            mp: Some(50),
+            max_mp: Some(100),
+            zuly: Some(1000),
+            zone: Some(1),
+            party_members: Some(2),
+        };
+        for name in TITLE_VARIABLES
+            .iter()
+            .filter(|&&name| name != "alias" && name != "slot")
+        {
+            assert!(
+                title_variable(&character, &JobNames::default(), name).is_some(),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn presets_are_valid_templates() {
+        let all = TitleOptions {
+            show_username: true,
+            show_job: true,
+            show_level: true,
+            show_hp: true,
+            show_mp: true,
+            show_zone: true,
+            show_zuly: true,
+        };
+        assert!(parse_title_template(&all.template_source()).is_ok());
+        assert_eq!(
+            TitleOptions::default().template_source(),
+            "{name}{?alias} ({alias}){/} - {job|Unknown}"
+        );
+    }
+
+    #[test]
+    fn formats_short_jobs_and_missing_values() {
+        let character = Character {
+            name: "Xikeon".into(),
+            job_id: 222,
+            level: Some(90),
+            hp: Some(600),
+            max_hp: Some(800),
+            party_members: Some(0),
+            ..Default::default()
+        };
+        let template =
+            parse_title_template("{name} [{job_short}] Lv{level} {hp_pct}%{?party} P{party}{/}")
+                .unwrap();
+        assert_eq!(
+            format_title(&character, &JobNames::default(), &template),
+            "Xikeon [CLE] Lv90 75%"
+        );
+
+        let template = parse_title_template("{job_short|???} {mp_pct|-}%").unwrap();
+        let unknown = Character {
+            job_id: 4242,
+            ..character
+        };
+        assert_eq!(
+            format_title(&unknown, &JobNames::default(), &template),
+            "??? -%"
+        );
+
+        // fields without an offset in the profile fall back
+        let stats = TitleOptions {
+            show_level: true,
+            show_zone: true,
+            ..Default::default()
+        };
+        let name_only = Character {
+            name: "Xikeon".into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            format_title(&name_only, &JobNames::default(), &stats.template()),
+            "Xikeon - Visitor - Lv ? - Zone ?"
+        );
+    }
+
+    #[test]
+    fn uses_the_job_names_of_the_client() {
+        let data_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+        let job_names = JobNames::load(&data_dir, 1, crate::encoding::TextEncoding::Utf8).unwrap();
+        let template = parse_title_template("{name} - {job} [{job_short}]").unwrap();
+        let berserker = Character {
+            name: "Xikeon".into(),
+            job_id: 131,
+            ..Default::default()
+        };
+        assert_eq!(
+            format_title(&berserker, &job_names, &template),
+            "Xikeon - Berserker [BER]"
+        );
+        assert_eq!(
+            format_title(&berserker, &JobNames::default(), &template),
+            "Xikeon -  []"
+        );
+    }
+}
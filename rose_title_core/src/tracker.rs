@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use sysinfo::{System, SystemExt};
+
+use crate::character::Character;
+use crate::discovery::{find_game_processes, GAME_IMAGE_NAME};
+use crate::error::Error;
+use crate::jobs::{JobNames, DEFAULT_LANGUAGE};
+use crate::offsets::{OffsetsProfile, OffsetsProfiles};
+use crate::pe::ClientBuild;
+use crate::platform_api;
+use crate::process_memory::{self, MemoryReader};
+use crate::rules::RuleSet;
+use crate::scan_cache::{ScanCache, ScanCacheError};
+use crate::signatures::{SignatureDatabase, SignatureEntry};
+use crate::slots::{self, SlotAssignments, SlotsError};
+use crate::stb::StbError;
+use crate::template::Template;
+
+#[derive(Debug)]
+pub struct Game {
+    pub pid: u32,
+    /// Name of the player signature that matched in this client.
+    pub signature_name: Option<String>,
+    pub signature_address: usize,
+    pub player_address: usize,
+    pub window_handle: Option<usize>,
+    pub wine_prefix: Option<PathBuf>,
+    /// The executable of the client, its directory holds the game data.
+    pub image_path: Option<PathBuf>,
+    /// Read from the executable on disk when the client is first seen.
+    pub client_build: Option<ClientBuild>,
+    /// Offsets profile picked for the client build.
+    pub profile: OffsetsProfile,
+    /// Job names read from the client's data directory, only the built-in ones until
+    /// they are loaded.
+    pub job_names: Arc<JobNames>,
+    /// The character as of the last title update.
+    pub character: Option<Character>,
+    /// Multibox slot of the character, kept when it logs in on another client.
+    pub slot: Option<u32>,
+    pub title: String,
+    /// Whether a title rule leaves the window title alone.
+    pub excluded: bool,
+    /// Why the player couldn't be found or the window wasn't renamed on the last update.
+    pub last_error: Option<Error>,
+}
+
+impl Game {
+    /// Resolves the player address, reusing the signature address of `known` if given.
+    fn find_player(
+        &mut self,
+        known: Option<(&SignatureEntry, usize)>,
+        signatures: &SignatureDatabase,
+        scan_cache: &mut ScanCache,
+    ) -> Result<(), Error> {
+        let process = process_memory::open_process(self.pid)?;
+        process.module(GAME_IMAGE_NAME)?;
+
+        let (entry, trace) = match known {
+            Some((entry, signature_address)) => (
+                entry,
+                entry
+                    .player_path()
+                    .trace_after(&process, 0, signature_address),
+            ),
+            None => {
+                let found = scan_cache
+                    .find_player(signatures, &process, GAME_IMAGE_NAME)?
+                    .ok_or_else(|| Error::SignatureNotFound {
+                        module: GAME_IMAGE_NAME.into(),
+                    })?;
+                (found.entry, found.trace)
+            }
+        };
+
+        self.signature_name = Some(entry.name.clone());
+        self.signature_address = trace.addresses[0];
+        self.player_address = trace.into_result()?;
+        Ok(())
+    }
+
+    /// The directory of the executable, which holds the game data.
+    pub fn data_dir(&self) -> Option<&Path> {
+        self.image_path.as_deref()?.parent()
+    }
+
+    /// Reads the character and formats its title without touching the window,
+    /// `slot_of` gives the slot of the character's name.
+    fn read_title(
+        &mut self,
+        template: &Template,
+        rules: &RuleSet,
+        slot_of: impl FnOnce(&str) -> Option<u32>,
+    ) -> Result<(), Error> {
+        let process = process_memory::open_process(self.pid)?;
+        self.character = None;
+        self.slot = None;
+        let character = Character::read_profile(&process, self.player_address, &self.profile)?;
+        let slot = slot_of(&character.name);
+        let title = rules.format_title(&character, &self.job_names, slot, template);
+        self.character = Some(character);
+        self.slot = slot;
+
+        self.excluded = title.is_none();
+        self.title = title.unwrap_or_default();
+        Ok(())
+    }
+
+    fn update_title(
+        &mut self,
+        template: &Template,
+        rules: &RuleSet,
+        slots: &mut SlotAssignments,
+    ) -> Result<(), Error> {
+        self.read_title(template, rules, |name| slots.assign(name, slots::today()))?;
+        if self.excluded {
+            return Ok(());
+        }
+
+        let window_handle = self
+            .window_handle
+            .ok_or(Error::WindowNotFound { pid: self.pid })?;
+        if !platform_api::window_set_title(window_handle, &self.title) {
+            return Err(Error::WindowNotRenamed {
+                window: window_handle,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Keeps track of the running game clients and the titles of their windows.
+pub struct GameTracker {
+    system: System,
+    pub games: HashMap<u32, Game>,
+    pub signatures: SignatureDatabase,
+    pub profiles: OffsetsProfiles,
+    pub rules: RuleSet,
+    pub slots: SlotAssignments,
+    slots_path: Option<PathBuf>,
+    /// Why the slots couldn't be saved the last time they changed.
+    pub slots_error: Option<SlotsError>,
+    /// Language of the job names read from the client, `None` for the built-in names.
+    job_language: Option<usize>,
+    /// The job names of every client data directory seen so far, clients installed
+    /// in different directories can have different names.
+    job_names: HashMap<PathBuf, Arc<JobNames>>,
+    /// Why the job names couldn't be read from a data directory.
+    pub job_names_errors: HashMap<PathBuf, StbError>,
+    scan_cache: ScanCache,
+    scan_cache_path: Option<PathBuf>,
+    /// Why the scan cache couldn't be saved the last time it changed.
+    pub scan_cache_error: Option<ScanCacheError>,
+}
+
+impl Default for GameTracker {
+    fn default() -> Self {
+        GameTracker::new(SignatureDatabase::builtin(), OffsetsProfiles::builtin())
+    }
+}
+
+impl GameTracker {
+    pub fn new(signatures: SignatureDatabase, profiles: OffsetsProfiles) -> Self {
+        GameTracker {
+            system: System::new(),
+            games: HashMap::new(),
+            signatures,
+            profiles,
+            rules: RuleSet::default(),
+            slots: SlotAssignments::default(),
+            slots_path: None,
+            slots_error: None,
+            job_language: Some(DEFAULT_LANGUAGE),
+            job_names: HashMap::new(),
+            job_names_errors: HashMap::new(),
+            scan_cache: ScanCache::default(),
+            scan_cache_path: None,
+            scan_cache_error: None,
+        }
+    }
+
+    /// Keeps signature scan results in the file at `path` across restarts.
+    pub fn with_scan_cache(mut self, path: PathBuf) -> Self {
+        self.scan_cache = ScanCache::load_or_default(&path);
+        self.scan_cache_path = Some(path);
+        self
+    }
+
+    /// Keeps the slots of the characters in the file at `path` across restarts.
+    pub fn with_slots(mut self, path: PathBuf) -> Self {
+        self.slots = SlotAssignments::load_or_default(&path);
+        self.slots_path = Some(path);
+        self
+    }
+
+    pub fn with_rules(mut self, rules: RuleSet) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Reads the job names in `language` from the clients on the next update, or only
+    /// uses the built-in names for `None`.
+    pub fn set_job_language(&mut self, language: Option<usize>) {
+        self.job_language = language;
+        self.job_names.clear();
+        self.job_names_errors.clear();
+        for game in self.games.values_mut() {
+            game.job_names = Arc::default();
+        }
+    }
+
+    pub fn job_language(&self) -> Option<usize> {
+        self.job_language
+    }
+
+    /// The most complete job names of the clients, for title rules and previews
+    /// that aren't about one client.
+    pub fn any_job_names(&self) -> Arc<JobNames> {
+        self.games
+            .values()
+            .map(|game| game.job_names.clone())
+            .max_by_key(|names| names.len())
+            .unwrap_or_default()
+    }
+
+    /// Gives every client the job names of its data directory, reading the ones of
+    /// directories that weren't seen yet.
+    fn load_job_names(&mut self) {
+        let Some(language) = self.job_language else {
+            return;
+        };
+        for game in self.games.values_mut() {
+            let Some(data_dir) = game.data_dir() else {
+                continue;
+            };
+            if let Some(names) = self.job_names.get(data_dir) {
+                game.job_names = names.clone();
+                continue;
+            }
+            // a directory without the tables isn't read again until the language changes
+            if self.job_names_errors.contains_key(data_dir) {
+                continue;
+            }
+
+            let data_dir = data_dir.to_path_buf();
+            match JobNames::load(&data_dir, language, game.profile.encoding) {
+                Ok(names) => {
+                    let names = Arc::new(names);
+                    game.job_names = names.clone();
+                    self.job_names.insert(data_dir, names);
+                }
+                Err(error) => {
+                    self.job_names_errors.insert(data_dir, error);
+                }
+            }
+        }
+    }
+
+    pub fn find_games(&mut self) {
+        let system = &mut self.system;
+        let games = &mut self.games;
+        let signatures = &self.signatures;
+        let profiles = &self.profiles;
+        let scan_cache = &mut self.scan_cache;
+        system.refresh_all(); //.refresh_processes();
+
+        let mut found_pids: Vec<u32> = vec![];
+        for game_process in find_game_processes(system) {
+            let pid = game_process.pid;
+            found_pids.push(pid);
+
+            // if the game was found before and has a player address we can skip the signature scan
+            let known_signature = games
+                .get(&pid)
+                .filter(|old| old.player_address != 0)
+                .and_then(|old| {
+                    let entry = signatures.player_by_name(old.signature_name.as_deref()?)?;
+                    Some((entry, old.signature_address))
+                });
+
+            // the executable doesn't change while the client runs, only read it once
+            let client_build = match games.get(&pid) {
+                Some(old) => old.client_build.clone(),
+                None => game_process
+                    .image_path
+                    .as_deref()
+                    .and_then(|path| ClientBuild::load(path).ok()),
+            };
+            let file_version = client_build
+                .as_ref()
+                .and_then(|build| build.file_version.as_deref());
+            let profile = profiles.select(file_version).clone();
+            // shown until the next title update reads them again
+            let old = games.get(&pid).filter(|_| known_signature.is_some());
+            let character = old.and_then(|old| old.character.clone());
+            let slot = old.and_then(|old| old.slot);
+
+            let mut game = Game {
+                pid,
+                signature_name: None,
+                signature_address: 0,
+                player_address: 0,
+                window_handle: platform_api::find_process_window(pid),
+                wine_prefix: game_process.wine_prefix,
+                image_path: game_process.image_path,
+                client_build,
+                profile,
+                job_names: games
+                    .get(&pid)
+                    .map(|old| old.job_names.clone())
+                    .unwrap_or_default(),
+                character,
+                slot,
+                title: "".into(),
+                excluded: false,
+                last_error: None,
+            };
+            game.last_error = game
+                .find_player(known_signature, signatures, scan_cache)
+                .err();
+            if game.last_error.is_some() {
+                game.character = None;
+                game.slot = None;
+            }
+            games.insert(pid, game);
+        }
+
+        // Remove windows that have been closed
+        games.retain(|&k, _| found_pids.contains(&k));
+
+        if let Some(path) = &self.scan_cache_path {
+            // a cache that can't be written only costs a scan on the next start, the
+            // error is shown in the debug report and the save retried on the next update
+            self.scan_cache_error = self.scan_cache.save_if_changed(path).err();
+        }
+        self.load_job_names();
+    }
+
+    pub fn set_titles(&mut self, template: &Template) {
+        let rules = &self.rules;
+        let slots = &mut self.slots;
+        for (_pid, game) in self.games.iter_mut() {
+            // the reason is already recorded by find_games
+            if game.player_address == 0 {
+                continue;
+            }
+
+            game.last_error = game.update_title(template, rules, slots).err();
+        }
+
+        // like the scan cache, slots that can't be saved are only lost on restart, the
+        // error is shown in the debug report
+        self.slots_error = self.save_slots().err();
+    }
+
+    /// Reads the characters and formats their titles like `set_titles`, without
+    /// renaming the windows or handing out slots.
+    pub fn read_titles(&mut self, template: &Template) {
+        for game in self.games.values_mut() {
+            // the reason is already recorded by find_games
+            if game.player_address == 0 {
+                continue;
+            }
+
+            game.last_error = game
+                .read_title(template, &self.rules, |name| self.slots.get(name))
+                .err();
+        }
+    }
+
+    /// Saves the slots if they changed, e.g. after releasing some of them.
+    pub fn save_slots(&mut self) -> Result<(), SlotsError> {
+        match &self.slots_path {
+            Some(path) => self.slots.save_if_changed(path),
+            None => Ok(()),
+        }
+    }
+
+    /// The games by slot, the ones without a character last by process id.
+    pub fn games_by_slot(&self) -> Vec<&Game> {
+        let mut games: Vec<&Game> = self.games.values().collect();
+        games.sort_by_key(|game| (game.slot.is_none(), game.slot, game.pid));
+        games
+    }
+}
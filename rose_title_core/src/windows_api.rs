@@ -0,0 +1,89 @@
+use std::mem;
+
+use widestring::U16String;
+use winapi::ctypes::c_void;
+use winapi::shared::minwindef::BOOL;
+use winapi::shared::minwindef::LPARAM;
+use winapi::shared::minwindef::TRUE;
+use winapi::shared::windef::HWND;
+use winapi::um::winuser::EnumWindows;
+use winapi::um::winuser::GetWindowThreadProcessId;
+use winapi::um::winuser::SendMessageW;
+use winapi::um::winuser::WM_GETTEXT;
+use winapi::um::winuser::WM_GETTEXTLENGTH;
+use winapi::um::winuser::WM_SETTEXT;
+
+pub fn window_get_title(hwnd: usize) -> String {
+    let text_length = unsafe { SendMessageW(hwnd as HWND, WM_GETTEXTLENGTH, 0, 0) + 1 };
+    let mut text_buffer = vec![0u16; text_length as usize];
+
+    let copied = unsafe {
+        SendMessageW(
+            hwnd as HWND,
+            WM_GETTEXT,
+            text_length as usize,
+            text_buffer.as_mut_ptr() as LPARAM,
+        )
+    };
+    text_buffer.truncate(copied as usize);
+
+    String::from_utf16_lossy(&text_buffer)
+}
+
+pub fn window_set_title(hwnd: usize, title: &str) -> bool {
+    if hwnd == 0 {
+        return false;
+    }
+
+    let title = U16String::from(title) + "\0";
+    unsafe { SendMessageW(hwnd as HWND, WM_SETTEXT, 0, title.as_ptr() as LPARAM) == TRUE as isize }
+}
+
+pub fn find_process_window(pid: u32) -> Option<usize> {
+    let mut maybe_window_handle = None;
+    enumerate_windows(|window| {
+        let window_process_id = window_thread_process_id(window).unwrap_or_default();
+
+        if window_process_id != pid {
+            return true;
+        }
+
+        maybe_window_handle = Some(window as usize);
+        return false;
+    });
+
+    maybe_window_handle
+}
+
+pub fn window_thread_process_id(hwnd: HWND) -> Option<u32> {
+    let mut window_process_id = 0;
+    unsafe {
+        GetWindowThreadProcessId(hwnd, &mut window_process_id);
+    }
+
+    if window_process_id > 0 {
+        Some(window_process_id)
+    } else {
+        None
+    }
+}
+
+pub fn enumerate_windows<F>(mut callback: F)
+where
+    F: FnMut(HWND) -> bool,
+{
+    let mut trait_obj: &mut dyn FnMut(HWND) -> bool = &mut callback;
+    let closure_pointer_pointer: *mut c_void = unsafe { mem::transmute(&mut trait_obj) };
+
+    let lparam = closure_pointer_pointer as LPARAM;
+    unsafe { EnumWindows(Some(enumerate_callback), lparam) };
+}
+
+unsafe extern "system" fn enumerate_callback(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let closure: &mut &mut dyn FnMut(HWND) -> bool = mem::transmute(lparam as *mut c_void);
+    if closure(hwnd) {
+        true.into()
+    } else {
+        false.into()
+    }
+}
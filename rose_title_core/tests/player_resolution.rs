@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+
+use rose_title_core::character::Character;
+use rose_title_core::error::Error;
+use rose_title_core::jobs::JobNames;
+use rose_title_core::offsets::OffsetsProfile;
+use rose_title_core::process_memory::{FakeProcess, PointerErrorKind};
+use rose_title_core::signatures::{SignatureDatabase, SignatureEntry};
+use rose_title_core::title::{format_title, TitleOptions};
+
+const MODULE_BASE: usize = 0x1_4000_0000;
+const PLAYER_ADDRESS: usize = 0x2_0000_0000;
+
+fn player_signature() -> SignatureEntry {
+    SignatureDatabase::builtin().player.remove(0)
+}
+
+fn fake_game(signature_offset: usize, pointer_offset: usize) -> FakeProcess {
+    let mut module = vec![0u8; 0x2000];
+    for (i, byte) in player_signature().pattern.split(' ').enumerate() {
+        module[signature_offset + i] = u8::from_str_radix(byte, 16).unwrap_or(0x48);
+    }
+    let displacement = (pointer_offset - signature_offset - 11) as u32;
+    module[signature_offset + 7..signature_offset + 11]
+        .copy_from_slice(&displacement.to_le_bytes());
+    module[pointer_offset..pointer_offset + 8]
+        .copy_from_slice(&(PLAYER_ADDRESS as u64).to_le_bytes());
+
+    let mut player = vec![0u8; 0x4000];
+    player[0x0B10..0x0B17].copy_from_slice(b"Xikeon\0");
+    player[0x3B1A..0x3B1E].copy_from_slice(&221u32.to_le_bytes());
+    player[0x3B1E..0x3B20].copy_from_slice(&90u16.to_le_bytes());
+    player[0x3B24..0x3B28].copy_from_slice(&1200i32.to_le_bytes());
+    player[0x3B28..0x3B2C].copy_from_slice(&1500i32.to_le_bytes());
+    player[0x3B40..0x3B48].copy_from_slice(&1_234_567i64.to_le_bytes());
+    player[0x0AF8..0x0AFA].copy_from_slice(&22u16.to_le_bytes());
+    player[0x3C20] = 0;
+
+    FakeProcess::new(1234)
+        .with_module("trose.exe", MODULE_BASE, module)
+        .with_region(PLAYER_ADDRESS, player)
+}
+
+#[test]
+fn resolves_player_from_signature() {
+    let process = fake_game(0x120, 0x1800);
+    let profile = OffsetsProfile::default();
+    let player_path = player_signature().player_path();
+
+    let trace = player_path.trace(&process, "trose.exe");
+    assert!(trace.error.is_none());
+    assert_eq!(trace.addresses[0], MODULE_BASE + 0x120);
+
+    let player_address = trace.into_result().unwrap();
+    assert_eq!(player_address, PLAYER_ADDRESS);
+
+    let character = Character::read_profile(&process, player_address, &profile).unwrap();
+    assert_eq!(character.name, "Xikeon");
+    assert_eq!(character.job_name(&JobNames::default()), "Mage");
+    // the builtin profile has no offsets for the other fields
+    assert_eq!((character.level, character.zone), (None, None));
+    assert_eq!(
+        format_title(
+            &character,
+            &JobNames::default(),
+            &TitleOptions::default().template()
+        ),
+        "Xikeon - Mage"
+    );
+}
+
+fn stats_profile() -> OffsetsProfile {
+    OffsetsProfile {
+        character_offsets: BTreeMap::from([
+            ("level".to_string(), 0x3B1E),
+            ("hp".to_string(), 0x3B24),
+            ("max_hp".to_string(), 0x3B28),
+            ("zuly".to_string(), 0x3B40),
+            ("zone".to_string(), 0x0AF8),
+            ("party_members".to_string(), 0x3C20),
+        ]),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn reads_fields_the_profile_has_offsets_for() {
+    let process = fake_game(0x120, 0x1800);
+    let character = Character::read_profile(&process, PLAYER_ADDRESS, &stats_profile()).unwrap();
+    assert_eq!((character.level, character.zone), (Some(90), Some(22)));
+    assert_eq!(character.hp_percent(), Some(80));
+    assert_eq!(character.zuly, Some(1_234_567));
+    assert_eq!(character.in_party(), Some(false));
+    assert_eq!(character.mp, None);
+}
+
+#[test]
+fn unreadable_fields_keep_name_and_job() {
+    let process = fake_game(0x120, 0x1800);
+    let mut profile = stats_profile();
+    profile.character_offsets.insert("level".into(), 0x8000);
+    profile.character_offsets.insert("mp".into(), 0x3FFE);
+
+    let character = Character::read_profile(&process, PLAYER_ADDRESS, &profile).unwrap();
+    assert_eq!((character.name.as_str(), character.job_id), ("Xikeon", 221));
+    assert_eq!((character.level, character.mp), (None, None));
+    assert_eq!(character.zone, Some(22));
+}
+
+#[test]
+fn unreadable_player_reports_what_was_read() {
+    let process = fake_game(0x120, 0x400);
+    let profile = OffsetsProfile::default();
+
+    let error = Character::read_profile(&process, 0x10, &profile).unwrap_err();
+    assert!(matches!(error.root(), Error::UnreadableAddress { .. }));
+    assert!(error
+        .to_string()
+        .starts_with("reading the character: address"));
+}
+
+#[test]
+fn null_player_pointer_reports_failing_step() {
+    let process = FakeProcess::new(1234).with_module("trose.exe", MODULE_BASE, vec![0; 32]);
+    let error = player_signature()
+        .player_path()
+        .resolve_after(&process, 0, MODULE_BASE)
+        .unwrap_err();
+    assert!(matches!(error.kind, PointerErrorKind::NullPointer { .. }));
+}
+
+#[test]
+fn falls_back_to_later_signatures() {
+    let process = fake_game(0x120, 0x1800);
+    let mut database = SignatureDatabase::builtin();
+    database.player.insert(
+        0,
+        SignatureEntry {
+            name: "newer-client".into(),
+            pattern: "48 8B 0D ? ? ? ? E8".into(),
+            displacement_offset: 3,
+            instruction_length: 7,
+            known_builds: vec![],
+        },
+    );
+
+    let found = database
+        .find_player(&process, "trose.exe")
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.entry.name, player_signature().name);
+    assert_eq!(found.trace.into_result().unwrap(), PLAYER_ADDRESS);
+
+    database.player.remove(1);
+    assert!(database
+        .find_player(&process, "trose.exe")
+        .unwrap()
+        .is_none());
+    assert!(matches!(
+        database.find_player(&process, "ntdll.dll"),
+        Err(Error::ModuleNotFound { .. })
+    ));
+}
+
+#[test]
+fn formats_selected_title_parts() {
+    let character = Character {
+        name: "Xikeon".into(),
+        job_id: 221,
+        level: Some(90),
+        hp: Some(1200),
+        max_hp: Some(1500),
+        zuly: Some(5000),
+        ..Default::default()
+    };
+    let name_only = TitleOptions {
+        show_job: false,
+        ..Default::default()
+    };
+    assert_eq!(
+        format_title(&character, &JobNames::default(), &name_only.template()),
+        "Xikeon"
+    );
+
+    let job_only = TitleOptions {
+        show_username: false,
+        ..Default::default()
+    };
+    let unknown_job = Character {
+        job_id: 4242,
+        ..character.clone()
+    };
+    assert_eq!(
+        format_title(&unknown_job, &JobNames::default(), &job_only.template()),
+        "Unknown"
+    );
+
+    let stats = TitleOptions {
+        show_level: true,
+        show_hp: true,
+        show_zuly: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        format_title(&character, &JobNames::default(), &stats.template()),
+        "Xikeon - Mage - Lv 90 - HP 1200/1500 - 5000 zuly"
+    );
+    assert_eq!(
+        format_title(
+            &Character {
+                level: None,
+                ..character
+            },
+            &JobNames::default(),
+            &stats.template()
+        ),
+        "Xikeon - Mage - Lv ? - HP 1200/1500 - 5000 zuly"
+    );
+}
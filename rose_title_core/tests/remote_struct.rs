@@ -0,0 +1,179 @@
+use rose_title_core::encoding::TextEncoding;
+use rose_title_core::error::Error;
+use rose_title_core::process_memory::FakeProcess;
+use rose_title_core::remote::{Layout, RemoteStruct};
+
+const PLAYER_ADDRESS: usize = 0x2_0000_0000;
+const TARGET_ADDRESS: usize = 0x3_0000_0000;
+
+#[derive(Debug, PartialEq, RemoteStruct)]
+struct Target {
+    #[offset(0x08)]
+    #[string(8)]
+    name: String,
+    #[offset(0x00)]
+    hp: i32,
+}
+
+#[derive(Debug, PartialEq, RemoteStruct)]
+struct Player {
+    #[offset(0x10)]
+    #[string(16)]
+    name: String,
+    #[offset(0x30)]
+    level: u16,
+    #[offset(0x34)]
+    speed: f32,
+    #[offset(0x38)]
+    zuly: i64,
+    #[offset(0x40)]
+    #[pointer]
+    target: Option<Target>,
+    #[offset(0x48)]
+    #[pointer]
+    party: Option<Target>,
+}
+
+fn player_memory() -> Vec<u8> {
+    let mut memory = vec![0; 0x50];
+    memory[0x10..0x16].copy_from_slice(b"Xikeon");
+    memory[0x30..0x32].copy_from_slice(&90u16.to_le_bytes());
+    memory[0x34..0x38].copy_from_slice(&1.5f32.to_le_bytes());
+    memory[0x38..0x40].copy_from_slice(&(-1234i64).to_le_bytes());
+    memory[0x40..0x48].copy_from_slice(&(TARGET_ADDRESS as u64).to_le_bytes());
+    memory
+}
+
+fn target_memory(name: &[u8]) -> Vec<u8> {
+    let mut memory = vec![0; 0x10];
+    memory[0x00..0x04].copy_from_slice(&350i32.to_le_bytes());
+    memory[0x08..0x08 + name.len()].copy_from_slice(name);
+    memory
+}
+
+#[test]
+fn reads_fields_and_follows_pointers() {
+    let process = FakeProcess::new(1)
+        .with_region(PLAYER_ADDRESS, player_memory())
+        .with_region(TARGET_ADDRESS, target_memory(b"Jelly"));
+
+    let player = Player::read(&process, PLAYER_ADDRESS).unwrap();
+    assert_eq!(
+        player,
+        Player {
+            name: "Xikeon".into(),
+            level: 90,
+            speed: 1.5,
+            zuly: -1234,
+            target: Some(Target {
+                name: "Jelly".into(),
+                hp: 350,
+            }),
+            party: None,
+        }
+    );
+}
+
+#[test]
+fn reads_only_the_span_of_the_fields() {
+    // nothing before 0x10 or after 0x50 is mapped
+    let process = FakeProcess::new(1)
+        .with_region(PLAYER_ADDRESS + 0x10, player_memory()[0x10..].to_vec())
+        .with_region(TARGET_ADDRESS, target_memory(b"Jelly"));
+
+    assert_eq!(Player::read(&process, PLAYER_ADDRESS).unwrap().level, 90);
+}
+
+#[test]
+fn reads_with_runtime_layout() {
+    let mut memory = player_memory();
+    memory[0x20..0x22].copy_from_slice(&91u16.to_le_bytes());
+    let process = FakeProcess::new(1)
+        .with_region(PLAYER_ADDRESS, memory)
+        .with_region(TARGET_ADDRESS, target_memory(&[0xC8, 0xAB, 0xB1, 0xE6]));
+
+    let mut layout = Layout::of::<Player>().with_encoding(TextEncoding::Cp949);
+    assert!(layout.set_offset("level", 0x20));
+    assert!(!layout.set_offset("job", 0x20));
+    assert_eq!(layout.offset("level"), Some(0x20));
+
+    let player = Player::read_layout(&process, PLAYER_ADDRESS, &layout).unwrap();
+    assert_eq!(player.level, 91);
+    // nested structs are read with the same encoding
+    assert_eq!(player.target.unwrap().name, "홍길");
+}
+
+#[test]
+fn reports_unreadable_structs_and_invalid_strings() {
+    let process = FakeProcess::new(1)
+        .with_region(PLAYER_ADDRESS, player_memory())
+        .with_region(TARGET_ADDRESS, target_memory(b"\xFFbad"));
+
+    assert!(matches!(
+        Player::read(&process, PLAYER_ADDRESS + 0x100),
+        Err(Error::UnreadableAddress { .. })
+    ));
+    assert!(matches!(
+        Player::read(&process, PLAYER_ADDRESS),
+        Err(Error::InvalidText {
+            address,
+            encoding: TextEncoding::Utf8,
+        }) if address == TARGET_ADDRESS + 0x08
+    ));
+
+    // the name fills its whole buffer without a terminator
+    let process = FakeProcess::new(1)
+        .with_region(PLAYER_ADDRESS, player_memory())
+        .with_region(TARGET_ADDRESS, target_memory(b"Jellyfis"));
+    assert!(matches!(
+        Player::read(&process, PLAYER_ADDRESS),
+        Err(Error::StringTooLong {
+            address,
+            max_length: 7,
+        }) if address == TARGET_ADDRESS + 0x08
+    ));
+}
+
+#[derive(Debug, PartialEq, RemoteStruct)]
+struct Stats {
+    #[offset(0x00)]
+    #[string(8)]
+    name: String,
+    #[offset(0x1000)]
+    job_id: u32,
+    level: Option<u16>,
+    #[offset(0x10)]
+    zuly: Option<i64>,
+}
+
+#[test]
+fn reads_distant_fields_separately() {
+    let mut first = vec![0; 0x18];
+    first[..6].copy_from_slice(b"Xikeon");
+    first[0x08..0x0A].copy_from_slice(&90u16.to_le_bytes());
+    first[0x10..0x18].copy_from_slice(&5000i64.to_le_bytes());
+    // nothing is mapped between the name and the job
+    let process = FakeProcess::new(1)
+        .with_region(PLAYER_ADDRESS, first)
+        .with_region(PLAYER_ADDRESS + 0x1000, 221u32.to_le_bytes().to_vec());
+
+    let stats = Stats::read(&process, PLAYER_ADDRESS).unwrap();
+    assert_eq!(
+        stats,
+        Stats {
+            name: "Xikeon".into(),
+            job_id: 221,
+            level: None,
+            zuly: Some(5000),
+        }
+    );
+
+    let mut layout = Layout::of::<Stats>();
+    assert_eq!(layout.offset("level"), None);
+    assert!(layout.has_field("level"));
+    layout.set_offset("level", 0x08);
+    layout.set_offset("zuly", 0x2000);
+    let stats = Stats::read_layout(&process, PLAYER_ADDRESS, &layout).unwrap();
+    // an unreadable optional field doesn't fail the struct
+    assert_eq!((stats.level, stats.zuly), (Some(90), None));
+}
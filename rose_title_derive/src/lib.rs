@@ -0,0 +1,226 @@
+//! `#[derive(RemoteStruct)]` for structs read out of the game's memory, see
+//! `rose_title_core::remote` for the runtime side.
+//!
+//! ```ignore
+//! #[derive(RemoteStruct)]
+//! struct Player {
+//!     #[offset(0x0B10)]
+//!     #[string(32)]
+//!     name: String,
+//!     #[offset(0x3B1A)]
+//!     job_id: u16,
+//!     #[offset(0x3C00)]
+//!     #[pointer]
+//!     target: Option<Target>,
+//!     // only read when a layout gives it an offset
+//!     level: Option<u16>,
+//! }
+//! ```
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Fields, GenericArgument, LitInt, PathArguments,
+    Type,
+};
+
+#[proc_macro_derive(RemoteStruct, attributes(offset, string, pointer))]
+pub fn derive_remote_struct(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+enum FieldKind {
+    /// Any `RemoteValue`, e.g. integers and floats.
+    Value,
+    /// A string stored inline in a buffer of this many bytes.
+    String(LitInt),
+    /// A 64-bit pointer to another remote struct, `None` when null.
+    Pointer(Box<Type>),
+    /// An `Option` of a `RemoteValue`, read on its own and `None` when it can't be.
+    Optional(Box<Type>),
+}
+
+struct RemoteField {
+    ident: syn::Ident,
+    ty: Type,
+    /// Only optional fields may leave it to the layout.
+    offset: Option<LitInt>,
+    kind: FieldKind,
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "RemoteStruct can't be derived for generic structs",
+        ));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    name,
+                    "RemoteStruct needs a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                name,
+                "RemoteStruct can only be derived for structs",
+            ))
+        }
+    };
+    if fields.is_empty() {
+        return Err(Error::new_spanned(
+            name,
+            "RemoteStruct needs at least one field",
+        ));
+    }
+
+    let fields = fields
+        .iter()
+        .map(parse_field)
+        .collect::<Result<Vec<RemoteField>, Error>>()?;
+
+    let layouts = fields.iter().map(|field| {
+        let field_name = field.ident.to_string();
+        let offset = match &field.offset {
+            Some(offset) => quote!(::std::option::Option::Some(#offset)),
+            None => quote!(::std::option::Option::None),
+        };
+        let size = match &field.kind {
+            FieldKind::Value => {
+                let ty = &field.ty;
+                quote!(<#ty as ::rose_title_core::remote::RemoteValue>::SIZE)
+            }
+            FieldKind::Optional(ty) => {
+                quote!(<#ty as ::rose_title_core::remote::RemoteValue>::SIZE)
+            }
+            FieldKind::String(length) => quote!(#length),
+            FieldKind::Pointer(_) => quote!(8),
+        };
+        let optional = matches!(field.kind, FieldKind::Optional(_));
+        quote! {
+            ::rose_title_core::remote::FieldLayout {
+                name: #field_name,
+                offset: #offset,
+                size: #size,
+                optional: #optional,
+            }
+        }
+    });
+
+    let decoders = fields.iter().enumerate().map(|(index, field)| {
+        let ident = &field.ident;
+        let value = match &field.kind {
+            FieldKind::Value => quote!(fields.value(#index)),
+            FieldKind::Optional(ty) => quote!(fields.optional::<#ty>(#index)),
+            FieldKind::String(_) => quote!(fields.string(#index)?),
+            FieldKind::Pointer(target) => quote! {
+                match fields.pointer(#index) {
+                    0 => None,
+                    address => Some(
+                        <#target as ::rose_title_core::remote::RemoteStruct>::read_layout(
+                            process,
+                            address,
+                            &::rose_title_core::remote::Layout::of::<#target>()
+                                .with_encoding(fields.encoding()),
+                        )?,
+                    ),
+                }
+            },
+        };
+        quote!(#ident: #value)
+    });
+
+    Ok(quote! {
+        impl ::rose_title_core::remote::RemoteStruct for #name {
+            const FIELDS: &'static [::rose_title_core::remote::FieldLayout] = &[#(#layouts),*];
+
+            fn decode(
+                process: &impl ::rose_title_core::process_memory::MemoryReader,
+                fields: &::rose_title_core::remote::FieldBytes,
+            ) -> ::std::result::Result<Self, ::rose_title_core::error::Error> {
+                ::std::result::Result::Ok(#name {
+                    #(#decoders),*
+                })
+            }
+        }
+    })
+}
+
+fn parse_field(field: &syn::Field) -> Result<RemoteField, Error> {
+    let ident = field.ident.clone().expect("named field");
+    let mut offset = None;
+    let mut string = None;
+    let mut pointer = false;
+
+    for attr in &field.attrs {
+        if attr.path().is_ident("offset") {
+            offset = Some(attr.parse_args::<LitInt>()?);
+        } else if attr.path().is_ident("string") {
+            string = Some(attr.parse_args::<LitInt>()?);
+        } else if attr.path().is_ident("pointer") {
+            attr.meta.require_path_only()?;
+            pointer = true;
+        }
+    }
+
+    let kind = match (string, pointer) {
+        (Some(_), true) => {
+            return Err(Error::new_spanned(
+                &ident,
+                "a field can't be both #[string] and #[pointer]",
+            ))
+        }
+        (Some(length), false) => FieldKind::String(length),
+        (None, true) => {
+            FieldKind::Pointer(option_inner(&field.ty).map(Box::new).ok_or_else(|| {
+                Error::new_spanned(&field.ty, "#[pointer] fields have to be an Option<T>")
+            })?)
+        }
+        (None, false) => match option_inner(&field.ty) {
+            Some(inner) => FieldKind::Optional(Box::new(inner)),
+            None => FieldKind::Value,
+        },
+    };
+    if offset.is_none() && !matches!(kind, FieldKind::Optional(_)) {
+        return Err(Error::new_spanned(
+            &ident,
+            "missing #[offset(..)] attribute, only Option fields can leave it to the layout",
+        ));
+    }
+
+    Ok(RemoteField {
+        ident,
+        ty: field.ty.clone(),
+        offset,
+        kind,
+    })
+}
+
+/// `T` of an `Option<T>` type.
+fn option_inner(ty: &Type) -> Option<Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    match arguments.args.first()? {
+        GenericArgument::Type(inner) => Some(inner.clone()),
+        _ => None,
+    }
+}
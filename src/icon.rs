@@ -1,37 +1,23 @@
-use std::mem;
-
 use eframe::IconData;
+
+#[cfg(windows)]
 use widestring::U16String;
-use winapi::ctypes::c_void;
-use winapi::shared::minwindef::BOOL;
-use winapi::shared::minwindef::LPARAM;
+#[cfg(windows)]
 use winapi::shared::windef::HDC;
+#[cfg(windows)]
 use winapi::shared::windef::HICON;
-use winapi::shared::windef::HWND;
+#[cfg(windows)]
 use winapi::um::libloaderapi::GetModuleHandleW;
-use winapi::um::wingdi::CreateCompatibleDC;
-use winapi::um::wingdi::DeleteDC;
-use winapi::um::wingdi::GetDIBits;
-use winapi::um::wingdi::GetObjectA;
-use winapi::um::wingdi::SelectObject;
-use winapi::um::wingdi::BITMAP;
-use winapi::um::wingdi::BITMAPINFO;
-use winapi::um::wingdi::BITMAPINFOHEADER;
-use winapi::um::wingdi::BI_RGB;
-use winapi::um::wingdi::DIB_RGB_COLORS;
-use winapi::um::winuser::EnumWindows;
-use winapi::um::winuser::GetIconInfo;
-use winapi::um::winuser::GetWindowThreadProcessId;
-use winapi::um::winuser::LoadImageW;
-use winapi::um::winuser::SendMessageW;
-use winapi::um::winuser::ICONINFO;
-use winapi::um::winuser::IMAGE_ICON;
-use winapi::um::winuser::LR_DEFAULTCOLOR;
-use winapi::um::winuser::WM_GETTEXT;
-use winapi::um::winuser::WM_GETTEXTLENGTH;
-use winapi::um::winuser::WM_SETTEXT;
+#[cfg(windows)]
+use winapi::um::wingdi::{
+    CreateCompatibleDC, DeleteDC, GetDIBits, GetObjectA, SelectObject, BITMAP, BITMAPINFO,
+    BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+};
+#[cfg(windows)]
+use winapi::um::winuser::{GetIconInfo, LoadImageW, ICONINFO, IMAGE_ICON, LR_DEFAULTCOLOR};
 
 // Grab the icon from the exe and hand it over to egui
+#[cfg(windows)]
 pub fn load_app_icon() -> IconData {
     let (mut buffer, width, height) = unsafe {
         let h_instance = GetModuleHandleW(0 as *const u16); //.expect("Failed to get HINSTANCE");
@@ -127,62 +113,26 @@ pub fn load_app_icon() -> IconData {
     }
 }
 
-pub fn window_get_title(hwnd: usize) -> String {
-    let text_length = unsafe { SendMessageW(hwnd as HWND, WM_GETTEXTLENGTH, 0, 0) + 1 };
-    let mut text_buffer = Vec::<u16>::with_capacity(text_length as usize);
-
-    unsafe {
-        SendMessageW(
-            hwnd as HWND,
-            WM_GETTEXT,
-            text_length as usize,
-            text_buffer.as_mut_ptr() as LPARAM,
-        );
-    }
+#[cfg(target_os = "linux")]
+static ICON_ICO: &[u8] = include_bytes!("../icon.ico");
 
-    String::from_utf16_lossy(&text_buffer)
-}
-
-pub fn window_set_title(hwnd: usize, title: &str) {
-    if hwnd == 0 {
-        return;
-    }
-
-    let title = U16String::from(title) + "\0";
-    unsafe {
-        SendMessageW(hwnd as HWND, WM_SETTEXT, 0, title.as_ptr() as LPARAM);
-    }
-}
-
-pub fn window_thread_process_id(hwnd: HWND) -> Option<u32> {
-    let mut window_process_id = 0;
-    unsafe {
-        GetWindowThreadProcessId(hwnd, &mut window_process_id);
-    }
-
-    if window_process_id > 0 {
-        Some(window_process_id)
-    } else {
-        None
-    }
-}
-
-pub fn enumerate_windows<F>(mut callback: F)
-where
-    F: FnMut(HWND) -> bool,
-{
-    let mut trait_obj: &mut dyn FnMut(HWND) -> bool = &mut callback;
-    let closure_pointer_pointer: *mut c_void = unsafe { mem::transmute(&mut trait_obj) };
+// There are no exe resources on Linux, so decode the PNG stored inside icon.ico instead
+#[cfg(target_os = "linux")]
+pub fn load_app_icon() -> IconData {
+    // ICONDIR (6 bytes) followed by the first ICONDIRENTRY, which holds the image offset at 12..16
+    let image_offset = u32::from_le_bytes(ICON_ICO[18..22].try_into().unwrap()) as usize;
 
-    let lparam = closure_pointer_pointer as LPARAM;
-    unsafe { EnumWindows(Some(enumerate_callback), lparam) };
-}
+    let decoder = png::Decoder::new(&ICON_ICO[image_offset..]);
+    let mut reader = decoder.read_info().expect("Failed to read icon");
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buffer)
+        .expect("Failed to decode icon");
+    buffer.truncate(info.buffer_size());
 
-unsafe extern "system" fn enumerate_callback(hwnd: HWND, lparam: LPARAM) -> BOOL {
-    let closure: &mut &mut dyn FnMut(HWND) -> bool = mem::transmute(lparam as *mut c_void);
-    if closure(hwnd) {
-        true.into()
-    } else {
-        false.into()
+    IconData {
+        rgba: buffer,
+        width: info.width,
+        height: info.height,
     }
 }
@@ -4,22 +4,28 @@ use core::time;
 use eframe::egui::{self, RichText, TextEdit, TextStyle};
 use eframe::epaint::{FontFamily, FontId};
 use eframe::Theme;
-use std::collections::HashMap;
+use icon::load_app_icon;
+use rose_title_core::character::Character;
+use rose_title_core::helpers::get_debug_info;
+use rose_title_core::jobs::{JobNames, LANGUAGES};
+use rose_title_core::offsets::OffsetsProfiles;
+use rose_title_core::rules::{self, MatchKind, RuleSet, TitleRule, TitleRules};
+use rose_title_core::scan_cache;
+use rose_title_core::signatures::SignatureDatabase;
+use rose_title_core::slots;
+use rose_title_core::template::Template;
+use rose_title_core::title::{parse_title_template, TitleOptions, TITLE_VARIABLES};
+use rose_title_core::tracker::GameTracker;
 use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
-use sysinfo::{PidExt, ProcessExt, System, SystemExt};
 use tray_item::TrayItem;
-use windows_api::load_app_icon;
 
-mod helpers;
-mod process_memory;
-mod windows_api;
-use crate::helpers::*;
+mod icon;
 
 fn main() {
     let icon_data = load_app_icon();
     let options = eframe::NativeOptions {
-        initial_window_size: Some(egui::vec2(320.0, 240.0)),
+        initial_window_size: Some(egui::vec2(640.0, 320.0)),
         resizable: false,
         follow_system_theme: false,
         default_theme: Theme::Dark,
@@ -59,13 +65,48 @@ fn configure_text_styles(ctx: &egui::Context) {
     ctx.set_style(style);
 }
 
-#[derive(Debug)]
-struct Game {
-    pid: u32,
-    signature_address: usize,
-    player_address: usize,
-    window_handle: Option<usize>,
-    title: String,
+const CHARACTER_COLUMNS: [&str; 6] = ["lv", "hp", "mp", "zone", "zuly", "party"];
+
+/// The values of `CHARACTER_COLUMNS`, empty while the character isn't known.
+fn character_columns(character: Option<&Character>) -> [String; 6] {
+    let Some(character) = character else {
+        return Default::default();
+    };
+    [
+        known(character.level),
+        format!("{}/{}", known(character.hp), known(character.max_hp)),
+        format!("{}/{}", known(character.mp), known(character.max_mp)),
+        known(character.zone),
+        known(character.zuly),
+        match character.party_members {
+            Some(0) => "-".into(),
+            members => known(members),
+        },
+    ]
+}
+
+/// `?` for fields the offsets profile has no offset for.
+fn known<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| "?".into(), |value| value.to_string())
+}
+
+fn bottom_panel_frame() -> egui::Frame {
+    egui::Frame {
+        inner_margin: egui::style::Margin::same(8.0),
+        outer_margin: egui::style::Margin::same(0.0),
+        rounding: eframe::epaint::Rounding::none(),
+        shadow: eframe::epaint::Shadow::NONE,
+        fill: eframe::epaint::Color32::from_rgb(20, 20, 20),
+        stroke: eframe::epaint::Stroke::default(),
+    }
+}
+
+/// `language` with its first letter in upper case, for the menu.
+fn capitalize(language: &str) -> String {
+    let mut chars = language.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
 }
 
 enum TrayMessage {
@@ -78,37 +119,76 @@ struct MyApp {
     app_is_hidden: Arc<Mutex<bool>>,
     new_hidden_state: Arc<Mutex<bool>>,
     quit_app: Arc<Mutex<bool>>,
-    show_username: Arc<Mutex<bool>>,
-    show_job: Arc<Mutex<bool>>,
-    system: Arc<Mutex<System>>,
-    games: Arc<Mutex<HashMap<u32, Game>>>,
+    title_options: Arc<Mutex<TitleOptions>>,
+    /// Whether the template is used instead of the preset of `title_options`.
+    use_template: Arc<Mutex<bool>>,
+    template_text: Arc<Mutex<String>>,
+    /// The preset or the last valid template, used for the titles.
+    title_template: Arc<Mutex<Template>>,
+    tracker: Arc<Mutex<GameTracker>>,
     show_debug: Arc<Mutex<bool>>,
     debug_text: Arc<Mutex<String>>,
-    signature: Arc<Mutex<String>>,
+    show_rules: Arc<Mutex<bool>>,
+    /// The title rules as edited, applied when saved.
+    rules: Arc<Mutex<TitleRules>>,
+    rules_error: Arc<Mutex<Option<String>>>,
+    config_errors: Vec<String>,
 }
 
 impl MyApp {
     fn new(cc: &eframe::CreationContext) -> Self {
         configure_text_styles(&cc.egui_ctx);
+        // A broken offsets.toml, signatures.toml or rules.toml shouldn't keep the app from starting,
+        // fall back to the builtin ones
+        let mut config_errors = vec![];
+        let profiles = OffsetsProfiles::load_default().unwrap_or_else(|error| {
+            config_errors.push(error.to_string());
+            OffsetsProfiles::builtin()
+        });
+        let signatures = SignatureDatabase::load_default().unwrap_or_else(|error| {
+            config_errors.push(error.to_string());
+            SignatureDatabase::builtin()
+        });
+        let rules = TitleRules::load_default().unwrap_or_else(|error| {
+            config_errors.push(error.to_string());
+            TitleRules::default()
+        });
+        // no client was read yet, only the built-in job names are known
+        let rule_set = rules.compile(&JobNames::default()).unwrap_or_else(|error| {
+            config_errors.push(error.to_string());
+            RuleSet::default()
+        });
+        let mut tracker = GameTracker::new(signatures, profiles).with_rules(rule_set);
+        if let Some(path) = scan_cache::default_path() {
+            tracker = tracker.with_scan_cache(path);
+        }
+        if let Some(path) = slots::default_path() {
+            tracker = tracker.with_slots(path);
+        }
+
         // TODO: find a better way than wrapping everything in Arc/Mutex
         Self {
             app_is_hidden: Arc::new(Mutex::new(false)),
             new_hidden_state: Arc::new(Mutex::new(false)),
             quit_app: Arc::new(Mutex::new(false)),
-            show_username: Arc::new(Mutex::new(true)),
-            show_job: Arc::new(Mutex::new(true)),
-            system: Arc::new(Mutex::new(sysinfo::System::new())),
-            games: Arc::new(Mutex::new(HashMap::new())),
+            title_options: Arc::new(Mutex::new(TitleOptions::default())),
+            use_template: Arc::new(Mutex::new(false)),
+            template_text: Arc::new(Mutex::new(TitleOptions::default().template_source())),
+            title_template: Arc::new(Mutex::new(TitleOptions::default().template())),
+            tracker: Arc::new(Mutex::new(tracker)),
             show_debug: Arc::new(Mutex::new(false)),
             debug_text: Arc::new(Mutex::new("".into())),
-            signature: Arc::new(Mutex::new("? 83 EC 28 ? 8B 05 ? ? ? ? ? 85 C0 ? 24 ? 38 6B 00 00 ? ? ? ? ? ? 89 44 24 30 ? 85 C0".into())),
+            show_rules: Arc::new(Mutex::new(false)),
+            rules: Arc::new(Mutex::new(rules)),
+            rules_error: Arc::new(Mutex::new(None)),
+            config_errors,
         }
     }
 
     fn init_tray(&mut self, ectx: &egui::Context) {
         let me = self.clone();
         let ctx = ectx.clone();
-        thread::spawn(move || loop {
+        thread::spawn(move || {
             let mut tray = TrayItem::new("ROSE Title Changer", "tray-icon").unwrap();
 
             let (tx, rx) = mpsc::channel();
@@ -147,113 +227,92 @@ impl MyApp {
     }
 
     fn find_games(&mut self) {
-        let mut system = self.system.lock().unwrap();
-        let mut games = self.games.lock().unwrap();
-        system.refresh_all(); //.refresh_processes();
-
-        let mut found_pids: Vec<u32> = vec![];
-        for proc in system.processes_by_exact_name("trose.exe") {
-            found_pids.push(proc.pid().as_u32());
-
-            let maybe_process = process_memory::open_process(proc.pid().as_u32());
-            if maybe_process.is_none() {
-                continue;
-            }
-            let process = maybe_process.unwrap();
-
-            let signature_address;
-            if games.contains_key(&process.pid)
-                && games.get(&process.pid).unwrap().player_address != 0
-            {
-                // if the game was found before and has a player address we can skip the signature scan
-                let old = games.get(&process.pid).unwrap();
-                signature_address = old.signature_address;
-            } else {
-                let maybe_module = process.get_module_begin_end("trose.exe");
-                if maybe_module.is_none() {
-                    continue;
-                }
-                let (base_address, module_end) = maybe_module.unwrap();
-                let signature = self.signature.lock().unwrap();
-                signature_address =
-                    sig_scan(&process, &(*signature), base_address, module_end).unwrap_or(0);
-            }
-
-            let mut player_address = 0;
-            if signature_address != 0 {
-                let player_location_addr_offset =
-                    process.read_u32(signature_address + 0x07).unwrap_or(0) as usize;
-                if player_location_addr_offset != 0 {
-                    let player_location_addr = signature_address + player_location_addr_offset + 11;
-
-                    player_address =
-                        process.read_u64(player_location_addr as usize).unwrap_or(0) as usize;
-                }
-            }
+        self.tracker.lock().unwrap().find_games();
+    }
 
-            let window_handle = find_process_window(process.pid);
-
-            games.insert(
-                process.pid,
-                Game {
-                    pid: process.pid,
-                    signature_address,
-                    player_address,
-                    window_handle,
-                    title: "".into(),
-                },
-            );
+    /// Frees the slots of all characters, the running ones get the first slots again.
+    fn reset_slots(&mut self) {
+        let result = {
+            let mut tracker = self.tracker.lock().unwrap();
+            tracker.slots.clear();
+            tracker.save_slots()
+        };
+        if let Err(error) = result {
+            self.config_errors.push(error.to_string());
         }
+        self.set_titles();
+    }
 
-        // Remove windows that have been closed
-        games.retain(|&k, _| found_pids.contains(&k));
+    /// Reads the job names in `language` from the clients and updates the titles.
+    fn set_job_language(&mut self, language: Option<usize>) {
+        self.tracker.lock().unwrap().set_job_language(language);
+        self.find_games();
+        self.set_titles();
     }
 
     fn set_titles(&mut self) {
-        let mut games = self.games.lock().unwrap();
-        for (_pid, game) in games.iter_mut() {
-            if game.player_address == 0 {
-                continue;
-            }
-
-            let maybe_process = process_memory::open_process(game.pid);
-            if maybe_process.is_none() {
-                continue;
-            }
-            let process = maybe_process.unwrap();
-
-            let mut title_parts: Vec<String> = vec![];
-            let player_name = process
-                .read_string(game.player_address + 0x0B10)
-                .unwrap_or_default();
-            let player_job_id = process
-                .read_u32(game.player_address + 0x3B1A)
-                .unwrap_or_default();
-
-            {
-                let show_username = self.show_username.lock().unwrap();
-                let show_job = self.show_job.lock().unwrap();
-                if *show_username {
-                    title_parts.push(player_name);
-                }
+        let template = self.title_template.lock().unwrap().clone();
+        self.tracker.lock().unwrap().set_titles(&template);
+    }
 
-                if *show_job {
-                    title_parts.push(job_id_to_name(player_job_id));
-                }
+    /// Switches the titles to the preset or the template, unless the template is invalid.
+    fn update_template(&mut self) {
+        let template = if *self.use_template.lock().unwrap() {
+            match parse_title_template(&self.template_text.lock().unwrap()) {
+                Ok(template) => template,
+                Err(_) => return,
             }
+        } else {
+            self.title_options.lock().unwrap().template()
+        };
+        *self.title_template.lock().unwrap() = template;
+        self.set_titles();
+    }
 
-            game.title = title_parts.join(" - ");
+    /// The character and slot of the first window, or an example while there is none.
+    fn preview_character(&self) -> (Character, Option<u32>, Arc<JobNames>) {
+        let tracker = self.tracker.lock().unwrap();
+        tracker
+            .games_by_slot()
+            .into_iter()
+            .find_map(|game| Some((game.character.clone()?, game.slot, game.job_names.clone())))
+            .unwrap_or_else(|| {
+                let character = Character {
+                    name: "Xikeon".into(),
+                    job_id: 221,
+                    level: Some(90),
+                    hp: Some(1200),
+                    max_hp: Some(1500),
+                    mp: Some(300),
+                    max_mp: Some(400),
+                    zone: Some(2),
+                    zuly: Some(1_234_567),
+                    party_members: Some(3),
+                };
+                (character, Some(1), tracker.any_job_names())
+            })
+    }
 
-            if let Some(window_handle) = game.window_handle {
-                windows_api::window_set_title(window_handle, &game.title);
+    /// Applies the edited rules and saves them to rules.toml.
+    fn save_rules(&mut self) {
+        let rules = self.rules.lock().unwrap().clone();
+        let job_names = self.tracker.lock().unwrap().any_job_names();
+        let result = rules.compile(&job_names).and_then(|rule_set| {
+            self.tracker.lock().unwrap().rules = rule_set;
+            match rules::default_path() {
+                Some(path) => rules.save(&path),
+                None => Ok(()),
             }
-        }
+        });
+        *self.rules_error.lock().unwrap() = result.err().map(|error| error.to_string());
+        self.set_titles();
     }
 
     fn run_debug(&mut self) {
         let mut show_debug = self.show_debug.lock().unwrap();
         let mut debug_text = self.debug_text.lock().unwrap();
-        *debug_text = get_debug_info(&(*self.signature.lock().unwrap()));
+        let tracker = self.tracker.lock().unwrap();
+        *debug_text = get_debug_info(&tracker);
         *show_debug = true;
     }
 }
@@ -298,19 +357,12 @@ impl eframe::App for MyApp {
             if *show_debug {
                 egui::TopBottomPanel::bottom("debug_bottom")
                     .exact_height(34.0)
-                    .frame(egui::Frame {
-                        inner_margin: egui::style::Margin::same(8.0),
-                        outer_margin: egui::style::Margin::same(0.0),
-                        rounding: eframe::epaint::Rounding::none(),
-                        shadow: eframe::epaint::Shadow::NONE,
-                        fill: eframe::epaint::Color32::from_rgb(20, 20, 20),
-                        stroke: eframe::epaint::Stroke::default(),
-                    })
+                    .frame(bottom_panel_frame())
                     .show(ctx, |ui| {
                         ui.horizontal(|ui| {
                             if ui.button("Copy to clipboard").clicked() {
                                 let debug_text = self.debug_text.lock().unwrap();
-                                ui.output().copied_text = (&*debug_text).to_string();
+                                ui.output().copied_text = debug_text.to_string();
                             }
 
                             if ui.button("Close").clicked() {
@@ -334,6 +386,82 @@ impl eframe::App for MyApp {
             }
         }
 
+        // Title rules UI
+        if *self.show_rules.lock().unwrap() {
+            egui::TopBottomPanel::bottom("rules_bottom")
+                .exact_height(34.0)
+                .frame(bottom_panel_frame())
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        if ui.button("Add rule").clicked() {
+                            self.rules.lock().unwrap().rules.push(TitleRule::default());
+                        }
+                        if ui.button("Save").clicked() {
+                            self.save_rules();
+                        }
+                        if ui.button("Close").clicked() {
+                            *self.show_rules.lock().unwrap() = false;
+                        }
+                    })
+                });
+
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.heading("Title rules");
+                ui.label(
+                    RichText::new(
+                        "The first rule matching the character name, a regex on the name or \
+                         the job applies. An empty template keeps the one of the main window.",
+                    )
+                    .small(),
+                );
+                if let Some(error) = &*self.rules_error.lock().unwrap() {
+                    ui.colored_label(egui::Color32::LIGHT_RED, error);
+                }
+                ui.add_space(6.0);
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    let mut rules = self.rules.lock().unwrap();
+                    let mut removed = None;
+                    egui::Grid::new("rules").striped(true).show(ui, |ui| {
+                        for heading in
+                            ["match", "pattern", "alias", "prefix", "template", "exclude"]
+                        {
+                            ui.label(RichText::new(heading).strong());
+                        }
+                        ui.end_row();
+
+                        for (index, rule) in rules.rules.iter_mut().enumerate() {
+                            egui::ComboBox::from_id_source(("rule_kind", index))
+                                .selected_text(rule.kind.to_string())
+                                .width(70.0)
+                                .show_ui(ui, |ui| {
+                                    for kind in MatchKind::ALL {
+                                        ui.selectable_value(&mut rule.kind, kind, kind.to_string());
+                                    }
+                                });
+                            for (text, width) in [
+                                (&mut rule.pattern, 100.0),
+                                (&mut rule.alias, 70.0),
+                                (&mut rule.prefix, 50.0),
+                                (&mut rule.template, 150.0),
+                            ] {
+                                ui.add(TextEdit::singleline(text).desired_width(width));
+                            }
+                            ui.checkbox(&mut rule.exclude, "");
+                            if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                                removed = Some(index);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                    if let Some(index) = removed {
+                        rules.rules.remove(index);
+                    }
+                });
+            });
+            return;
+        }
+
         // Main UI
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP), |ui| {
@@ -341,6 +469,33 @@ impl eframe::App for MyApp {
                 ui.label(RichText::new("by Xikeon").small());
 
                 ui.menu_button(RichText::new("⬇"), |ui| {
+                    if ui.button("Title rules").clicked() {
+                        *self.show_rules.lock().unwrap() = true;
+                        ui.close_menu();
+                    }
+                    ui.menu_button("Job names", |ui| {
+                        let current = self.tracker.lock().unwrap().job_language();
+                        let choices = std::iter::once((None, "Built-in".to_string())).chain(
+                            LANGUAGES
+                                .iter()
+                                .enumerate()
+                                .map(|(index, name)| (Some(index), capitalize(name))),
+                        );
+                        for (language, label) in choices {
+                            if ui.radio(current == language, label).clicked() {
+                                self.set_job_language(language);
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                    if ui
+                        .button("Reset slots")
+                        .on_hover_text("Number the characters again as they are seen")
+                        .clicked()
+                    {
+                        self.reset_slots();
+                        ui.close_menu();
+                    }
                     if ui.button("Debug").clicked() {
                         self.run_debug();
                         ui.close_menu();
@@ -349,27 +504,85 @@ impl eframe::App for MyApp {
             });
             ui.add_space(10.0);
 
+            for config_error in &self.config_errors {
+                ui.colored_label(egui::Color32::LIGHT_RED, config_error);
+            }
+            if !self.config_errors.is_empty() {
+                ui.add_space(10.0);
+            }
+
             /*{
-                let mut signature = self.signature.lock().unwrap();
-                ui.text_edit_singleline(&mut *signature);
+                let mut tracker = self.tracker.lock().unwrap();
+                ui.text_edit_singleline(&mut tracker.signature);
             }*/
 
             {
-                let mut show_username = self.show_username.lock().unwrap();
-                if ui
-                    .checkbox(&mut show_username, "Show character name")
-                    .changed()
-                {
-                    drop(show_username);
-                    self.set_titles();
+                let mut use_template = *self.use_template.lock().unwrap();
+                let mut changed = false;
+                ui.horizontal(|ui| {
+                    ui.label("Title");
+                    changed |= ui.radio_value(&mut use_template, false, "Preset").changed();
+                    changed |= ui
+                        .radio_value(&mut use_template, true, "Template")
+                        .changed();
+                });
+                *self.use_template.lock().unwrap() = use_template;
+
+                if use_template {
+                    let mut template_text = self.template_text.lock().unwrap();
+                    changed |= ui
+                        .add(
+                            TextEdit::singleline(&mut *template_text)
+                                .desired_width(f32::INFINITY)
+                                .hint_text("{name} [{job_short}] Lv{level} {hp_pct}%"),
+                        )
+                        .on_hover_text(format!("Variables: {}", TITLE_VARIABLES.join(", ")))
+                        .changed();
+                } else {
+                    let mut title_options = self.title_options.lock().unwrap();
+                    let options = &mut *title_options;
+                    let mut preset_changed = false;
+                    ui.horizontal_wrapped(|ui| {
+                        for (show, label) in [
+                            (&mut options.show_username, "Character name"),
+                            (&mut options.show_job, "Job"),
+                            (&mut options.show_level, "Level"),
+                            (&mut options.show_hp, "HP"),
+                            (&mut options.show_mp, "MP"),
+                            (&mut options.show_zone, "Zone"),
+                            (&mut options.show_zuly, "Zuly"),
+                        ] {
+                            preset_changed |= ui.checkbox(show, label).changed();
+                        }
+                    });
+                    // switching to the template starts from the preset
+                    if preset_changed {
+                        *self.template_text.lock().unwrap() = options.template_source();
+                    }
+                    changed |= preset_changed;
                 }
-            }
 
-            {
-                let mut show_job = self.show_job.lock().unwrap();
-                if ui.checkbox(&mut show_job, "Show job").changed() {
-                    drop(show_job);
-                    self.set_titles();
+                if changed {
+                    self.update_template();
+                }
+
+                let template_text = self.template_text.lock().unwrap().clone();
+                match parse_title_template(&template_text) {
+                    Err(error) if use_template => {
+                        ui.colored_label(egui::Color32::LIGHT_RED, error.to_string());
+                    }
+                    _ => {
+                        let (character, slot, job_names) = self.preview_character();
+                        let template = self.title_template.lock().unwrap();
+                        let title = self
+                            .tracker
+                            .lock()
+                            .unwrap()
+                            .rules
+                            .format_title(&character, &job_names, slot, &template)
+                            .unwrap_or_else(|| "(excluded by a title rule)".into());
+                        ui.label(RichText::new(format!("Preview: {}", title)).small());
+                    }
                 }
             }
 
@@ -378,31 +591,107 @@ impl eframe::App for MyApp {
             ui.label("Detected windows");
             ui.separator();
 
+            // Only Wine hosted clients have a prefix, so the column is hidden on Windows
+            let show_prefix = self
+                .tracker
+                .lock()
+                .unwrap()
+                .games
+                .values()
+                .any(|game| game.wine_prefix.is_some());
+
             use egui_extras::{Column, TableBuilder};
-            TableBuilder::new(ui)
+            let mut table = TableBuilder::new(ui)
                 .striped(true)
-                .column(Column::auto().resizable(true).at_least(60.0))
-                .column(Column::remainder())
+                .column(Column::auto().at_least(20.0))
+                .column(Column::auto().resizable(true).at_least(60.0));
+            if show_prefix {
+                table = table.column(Column::initial(80.0).resizable(true).clip(true));
+            }
+            table
+                .column(Column::remainder().at_least(120.0))
+                .columns(Column::auto().at_least(30.0), CHARACTER_COLUMNS.len())
                 .header(24.0, |mut header| {
+                    header.col(|ui| {
+                        ui.label(RichText::new("#").text_style(tableheading()).strong());
+                    });
                     header.col(|ui| {
                         ui.label(RichText::new("pid").text_style(tableheading()).strong());
                     });
+                    if show_prefix {
+                        header.col(|ui| {
+                            ui.label(RichText::new("prefix").text_style(tableheading()).strong());
+                        });
+                    }
                     header.col(|ui| {
                         ui.label(RichText::new("title").text_style(tableheading()).strong());
                     });
+                    for column in CHARACTER_COLUMNS {
+                        header.col(|ui| {
+                            ui.label(RichText::new(column).text_style(tableheading()).strong());
+                        });
+                    }
                 })
                 .body(|body| {
-                    let games = self.games.lock().unwrap();
-                    let mut pids = games.keys();
-                    let num_rows = pids.len();
-                    body.rows(18.0, num_rows, |_row_index, mut row| {
-                        let pid = pids.next().unwrap();
+                    let tracker = self.tracker.lock().unwrap();
+                    let games = tracker.games_by_slot();
+                    body.rows(18.0, games.len(), |row_index, mut row| {
+                        let game = games[row_index];
                         row.col(|ui| {
-                            ui.label(games.get(pid).unwrap().pid.to_string());
+                            if let Some(slot) = game.slot {
+                                ui.label(slot.to_string());
+                            }
                         });
                         row.col(|ui| {
-                            ui.label(games.get(pid).unwrap().title.to_string());
+                            ui.label(game.pid.to_string());
+                        });
+                        if show_prefix {
+                            row.col(|ui| {
+                                if let Some(wine_prefix) = &game.wine_prefix {
+                                    ui.label(wine_prefix.display().to_string())
+                                        .on_hover_text(wine_prefix.display().to_string());
+                                }
+                            });
+                        }
+                        row.col(|ui| {
+                            let version = game
+                                .client_build
+                                .as_ref()
+                                .and_then(|build| build.file_version.as_deref())
+                                .unwrap_or("unknown");
+                            let signature = match &game.signature_name {
+                                Some(name) => format!("Signature: {}", name),
+                                None => "No signature matched".into(),
+                            };
+                            let mut details = format!(
+                                "Client version: {}\nOffsets profile: {}\n{}",
+                                version, game.profile.name, signature
+                            );
+                            match &game.last_error {
+                                Some(error) => {
+                                    details += &format!("\nError: {}", error);
+                                    let text = if game.title.is_empty() {
+                                        error.to_string()
+                                    } else {
+                                        game.title.to_string()
+                                    };
+                                    ui.colored_label(egui::Color32::LIGHT_RED, text)
+                                        .on_hover_text(details);
+                                }
+                                None if game.excluded => {
+                                    ui.label(RichText::new("excluded by a title rule").weak())
+                                        .on_hover_text(details);
+                                }
+                                None => {
+                                    ui.label(game.title.to_string()).on_hover_text(details);
+                                }
+                            }
                         });
+                        for value in character_columns(game.character.as_ref()) {
+                            row.col(|ui| {
+                                ui.label(value);
+                            });
+                        }
                     });
                 });
         });